@@ -1,9 +1,38 @@
+use std::path::{Path, PathBuf};
+
+#[path = "build/assets.rs"]
+mod assets;
+
 fn main() {
+    convert_assets();
+
+    // The linker scripts are only there for the ESP32-C3. Running the host tests uses the default
+    // linker setup.
+    if std::env::var("CARGO_CFG_TARGET_OS").as_deref() != Ok("none") {
+        return;
+    }
+
     linker_be_nice();
     // make sure linkall.x is the last linker script (otherwise might cause problems with flip-link)
     println!("cargo:rustc-link-arg=-Tlinkall.x");
 }
 
+/// Converts the images in `assets/` into patterns for [`hakkaa::assets`].
+fn convert_assets() {
+    println!("cargo:rerun-if-changed=assets");
+    println!("cargo:rerun-if-changed=build");
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let code = assets::convert_dir(Path::new("assets")).unwrap_or_else(|error| {
+        eprintln!();
+        eprintln!("💡 Converting the assets failed: {error}");
+        eprintln!();
+        std::process::exit(1);
+    });
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    std::fs::write(out_dir.join("assets.rs"), code).unwrap();
+}
+
 fn linker_be_nice() {
     let args: Vec<String> = std::env::args().collect();
     if args.len() > 1 {
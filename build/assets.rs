@@ -0,0 +1,372 @@
+//! Converting images into patterns for persistence of vision.
+//!
+//! The build script converts each image in `assets/` into a static array of frames, one frame per
+//! column of the image. The bottom row of the image is the LED at index 0. Dark pixels are LEDs
+//! switched on, light and transparent pixels are LEDs switched off.
+//!
+//! Images are PBM (plain or raw) or PNG files. Images with more or less than [`ROWS`] rows get
+//! scaled to fit, keeping their aspect ratio. Shades of gray are thresholded at half or, with the
+//! option `dither` in the file name like in `photo.dither.png`, dithered.
+//!
+//! This file is shared by the build script and the host tests in `tests/assets.rs`.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// The number of rows of a pattern, one for each storey LED.
+pub const ROWS: usize = 8;
+
+/// A grayscale image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    /// The width in pixels.
+    pub width: usize,
+    /// The height in pixels.
+    pub height: usize,
+    /// The ink of the pixels row by row from the top left, from 0 (blank) to 255 (black).
+    pub ink: Vec<u8>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize, ink: Vec<u8>) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err("the image is empty".into());
+        }
+        Ok(Self { width, height, ink })
+    }
+
+    fn ink(&self, x: usize, y: usize) -> u8 {
+        self.ink[y * self.width + x]
+    }
+}
+
+/// The image file formats.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Pbm,
+    Png,
+}
+
+/// How shades of gray turn into LEDs switched on or off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Conversion {
+    /// Pixels with at least half of the ink are on.
+    Threshold,
+    /// The shades are approximated by the density of the pixels switched on, with Floyd-Steinberg
+    /// dithering.
+    Dither,
+}
+
+/// An image file to convert.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Asset {
+    /// The name of the static for the pattern.
+    pub name: String,
+    pub format: Format,
+    pub conversion: Conversion,
+}
+
+impl Asset {
+    /// Returns the asset for the file `file_name` like `smile.pbm` or `photo.dither.png`. Returns
+    /// `None` for files which are no images, like a README.
+    pub fn from_file_name(file_name: &str) -> Result<Option<Self>, String> {
+        let mut parts: Vec<&str> = file_name.split('.').collect();
+        let format = match parts.pop().map(str::to_ascii_lowercase).as_deref() {
+            Some("pbm") if !parts.is_empty() => Format::Pbm,
+            Some("png") if !parts.is_empty() => Format::Png,
+            _ => return Ok(None),
+        };
+
+        let stem = parts.remove(0);
+        if !stem.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return Err(format!(
+                "{file_name}: asset names need to start with a letter"
+            ));
+        }
+        let name = stem
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+                _ => '_',
+            })
+            .collect();
+
+        let conversion = match parts.as_slice() {
+            [] => Conversion::Threshold,
+            ["dither"] => Conversion::Dither,
+            _ => return Err(format!("{file_name}: the only option is `dither`")),
+        };
+
+        Ok(Some(Self {
+            name,
+            format,
+            conversion,
+        }))
+    }
+}
+
+/// Decodes the image `data` in `format`.
+pub fn decode(format: Format, data: &[u8]) -> Result<Image, String> {
+    match format {
+        Format::Pbm => parse_pbm(data),
+        Format::Png => decode_png(data),
+    }
+}
+
+/// Parses a plain (`P1`) or raw (`P4`) PBM image. Its pixels are either blank or black.
+fn parse_pbm(data: &[u8]) -> Result<Image, String> {
+    let mut position = 0;
+
+    // Skips whitespace and comments and returns the next token of the header.
+    let token = |position: &mut usize| -> Result<&[u8], String> {
+        loop {
+            match data.get(*position) {
+                Some(b'#') => {
+                    while data.get(*position).is_some_and(|byte| *byte != b'\n') {
+                        *position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => *position += 1,
+                Some(_) => break,
+                None => return Err("the PBM header ends early".into()),
+            }
+        }
+        let start = *position;
+        while data
+            .get(*position)
+            .is_some_and(|byte| !byte.is_ascii_whitespace() && *byte != b'#')
+        {
+            *position += 1;
+        }
+        Ok(&data[start..*position])
+    };
+    let number = |position: &mut usize| -> Result<usize, String> {
+        let token = token(position)?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| {
+                format!(
+                    "invalid number {:?} in the PBM header",
+                    token.escape_ascii()
+                )
+            })
+    };
+
+    let magic = token(&mut position)?.to_vec();
+    let width = number(&mut position)?;
+    let height = number(&mut position)?;
+
+    let ink = match magic.as_slice() {
+        b"P1" => data[position..]
+            .iter()
+            .filter(|byte| !byte.is_ascii_whitespace())
+            .take(width * height)
+            .map(|byte| match *byte {
+                b'0' => Ok(0),
+                b'1' => Ok(255),
+                _ => Err(format!("invalid pixel {:?} in a plain PBM", *byte as char)),
+            })
+            .collect::<Result<Vec<u8>, String>>()?,
+        b"P4" => {
+            // A single whitespace character separates the header from the pixels.
+            let pixels = data.get(position + 1..).unwrap_or_default();
+            let stride = width.div_ceil(8);
+            if pixels.len() < stride * height {
+                return Err("the PBM image has too few pixels".into());
+            }
+            (0..width * height)
+                .map(|index| {
+                    let (y, x) = (index / width, index % width);
+                    if pixels[y * stride + x / 8] & (0x80 >> (x % 8)) != 0 {
+                        255
+                    } else {
+                        0
+                    }
+                })
+                .collect()
+        }
+        _ => return Err("only plain (P1) and raw (P4) PBM images are supported".into()),
+    };
+
+    if ink.len() != width * height {
+        return Err("the PBM image has too few pixels".into());
+    }
+    Image::new(width, height, ink)
+}
+
+/// Decodes a PNG image of any color type.
+fn decode_png(data: &[u8]) -> Result<Image, String> {
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|error| error.to_string())?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut buffer)
+        .map_err(|error| error.to_string())?;
+
+    let channels = info.color_type.samples();
+    let ink = buffer[..info.buffer_size()]
+        .chunks_exact(channels)
+        .map(|pixel| {
+            let (luma, alpha) = match *pixel {
+                [gray] => (u32::from(gray), 255),
+                [gray, alpha] => (u32::from(gray), u32::from(alpha)),
+                [r, g, b] => (luma(r, g, b), 255),
+                [r, g, b, alpha] => (luma(r, g, b), u32::from(alpha)),
+                _ => unreachable!("PNG pixels have up to four samples"),
+            };
+            // Transparent pixels have no ink.
+            ((255 - luma) * alpha / 255) as u8
+        })
+        .collect();
+
+    Image::new(info.width as usize, info.height as usize, ink)
+}
+
+/// Returns the brightness of a color as perceived by the eye.
+fn luma(r: u8, g: u8, b: u8) -> u32 {
+    (299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b)) / 1000
+}
+
+/// Scales `image` to `rows` rows, averaging the ink of the pixels which get merged.
+fn scale(image: &Image, rows: usize) -> Image {
+    if image.height == rows {
+        return image.clone();
+    }
+
+    let factor = rows as f64 / image.height as f64;
+    let width = ((image.width as f64 * factor).round() as usize).max(1);
+    let (step_x, step_y) = (
+        image.width as f64 / width as f64,
+        image.height as f64 / rows as f64,
+    );
+
+    // Returns how much of the pixel at `pixel` lies within `start..end`.
+    let overlap = |start: f64, end: f64, pixel: usize| {
+        (end.min(pixel as f64 + 1.0) - start.max(pixel as f64)).max(0.0)
+    };
+
+    let mut ink = Vec::with_capacity(width * rows);
+    for y in 0..rows {
+        let (top, bottom) = (y as f64 * step_y, (y + 1) as f64 * step_y);
+        for x in 0..width {
+            let (left, right) = (x as f64 * step_x, (x + 1) as f64 * step_x);
+            let (mut sum, mut area) = (0.0, 0.0);
+            for source_y in top as usize..(bottom.ceil() as usize).min(image.height) {
+                for source_x in left as usize..(right.ceil() as usize).min(image.width) {
+                    let weight = overlap(top, bottom, source_y) * overlap(left, right, source_x);
+                    sum += weight * f64::from(image.ink(source_x, source_y));
+                    area += weight;
+                }
+            }
+            ink.push((sum / area).round() as u8);
+        }
+    }
+
+    Image {
+        width,
+        height: rows,
+        ink,
+    }
+}
+
+/// Converts `image` into a pattern of [`ROWS`] LEDs with one column per frame. The bit at index
+/// _n_ of a column is the LED at index _n_, counting from the bottom of the image.
+pub fn convert(image: &Image, conversion: Conversion) -> Vec<u8> {
+    let image = scale(image, ROWS);
+    let mut on = vec![false; image.width * ROWS];
+
+    match conversion {
+        Conversion::Threshold => {
+            for (on, ink) in on.iter_mut().zip(&image.ink) {
+                *on = *ink >= 128;
+            }
+        }
+        Conversion::Dither => {
+            let mut ink: Vec<f32> = image.ink.iter().map(|ink| f32::from(*ink)).collect();
+            let width = image.width;
+            for y in 0..ROWS {
+                for x in 0..width {
+                    let index = y * width + x;
+                    on[index] = ink[index] >= 128.0;
+                    let error = ink[index] - if on[index] { 255.0 } else { 0.0 };
+
+                    let mut spread = |dx: isize, dy: usize, share: f32| {
+                        let x = x.checked_add_signed(dx).filter(|x| *x < width);
+                        if let (Some(x), true) = (x, y + dy < ROWS) {
+                            ink[(y + dy) * width + x] += error * share;
+                        }
+                    };
+                    spread(1, 0, 7.0 / 16.0);
+                    spread(-1, 1, 3.0 / 16.0);
+                    spread(0, 1, 5.0 / 16.0);
+                    spread(1, 1, 1.0 / 16.0);
+                }
+            }
+        }
+    }
+
+    (0..image.width)
+        .map(|x| {
+            (0..ROWS).fold(0, |column, led| {
+                let y = ROWS - 1 - led;
+                column | (u8::from(on[y * image.width + x]) << led)
+            })
+        })
+        .collect()
+}
+
+/// Returns the Rust code for a static named `name` with the frames for `columns`. `source` is the
+/// path of the image for the documentation.
+pub fn generate(name: &str, source: &str, columns: &[u8]) -> String {
+    let mut code = format!(
+        "/// Generated from `{source}`.\npub static {name}: [crate::frame::Frame; {}] = [\n",
+        columns.len()
+    );
+    for column in columns {
+        writeln!(code, "    crate::frame::Frame::from_bits(0b{column:08b}),").unwrap();
+    }
+    code.push_str("];\n");
+    code
+}
+
+/// Converts all images in `dir` and returns the Rust code for their patterns. A missing directory
+/// has no images. Images which would result in patterns with the same name are an error.
+pub fn convert_dir(dir: &Path) -> Result<String, String> {
+    let mut files = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| format!("{}: {error}", dir.display()))?,
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+
+    let mut code = String::new();
+    let mut sources: HashMap<String, String> = HashMap::new();
+    for path in files.iter().filter(|path| path.is_file()) {
+        let source = path.display().to_string();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let Some(asset) = Asset::from_file_name(&file_name)? else {
+            continue;
+        };
+        if let Some(other) = sources.insert(asset.name.clone(), source.clone()) {
+            return Err(format!(
+                "{other} and {source} both become the pattern {}",
+                asset.name
+            ));
+        }
+
+        let data = fs::read(path).map_err(|error| format!("{source}: {error}"))?;
+        let image = decode(asset.format, &data).map_err(|error| format!("{source}: {error}"))?;
+        code.push_str(&generate(
+            &asset.name,
+            &source,
+            &convert(&image, asset.conversion),
+        ));
+    }
+    Ok(code)
+}
@@ -0,0 +1,69 @@
+#![no_std]
+#![no_main]
+#![deny(
+    clippy::mem_forget,
+    reason = "mem::forget is generally not safe to do with esp_hal types, especially those \
+    holding buffers for the duration of a data transfer."
+)]
+
+use embassy_executor::Spawner;
+use embassy_time::{Duration, Ticker};
+use esp_backtrace as _;
+use hakkaa::board::{Board, Led};
+use hakkaa::dim::{Dimming, DimmingConfig};
+use hakkaa::led::Storeys;
+use hakkaa::pwm::{Duties, SoftPwm};
+use hakkaa::switch::Dimmable;
+
+// This creates a default app-descriptor required by the esp-idf bootloader.
+// For more information see: <https://docs.espressif.com/projects/esp-idf/en/stable/esp32/api-reference/system/app_image_format.html#application-description>
+esp_bootloader_esp_idf::esp_app_desc!();
+
+/// The number of LEDs on the board: the eight storey LEDs and the blue ESP LED.
+const LEDS: usize = 9;
+
+/// The duty cycles for the LEDs which don't get a hardware PWM channel. They are shared between
+/// the PWM task and the main task.
+static DUTIES: Duties<LEDS> = Duties::new();
+
+/// Task running the software PWM for the LEDs without a hardware PWM channel.
+#[embassy_executor::task]
+async fn pwm_task(mut pwm: SoftPwm<'static, Led<'static>, LEDS>) {
+    pwm.run().await;
+}
+
+#[esp_hal_embassy::main]
+async fn main(spawner: Spawner) -> ! {
+    let board = Board::init();
+
+    // Hand all LEDs over to the PWM peripherals and get dimmable LEDs in return. Running out of
+    // hardware channels, the remaining LEDs will be dimmed by software PWM.
+    let [d1, d2, d3, d4, d5, d6, d7, d8] = board.storey_leds;
+    let dimming = Dimming::new(
+        [d1, d2, d3, d4, d5, d6, d7, d8, board.esp_led],
+        board.ledc,
+        board.gpio_sd,
+        &DUTIES,
+        DimmingConfig::default(),
+    );
+    if let Some(pwm) = dimming.soft_pwm {
+        spawner.spawn(pwm_task(pwm)).unwrap();
+    }
+
+    let [c1, c2, c3, c4, c5, c6, c7, c8, mut esp_led] = dimming.leds;
+    let mut storeys = Storeys::new([c1, c2, c3, c4, c5, c6, c7, c8]);
+
+    log::info!("ハッカー the planet!");
+
+    // Show a brightness gradient along the storey LEDs and let it wander up. The levels are gamma
+    // corrected so the steps look even.
+    let mut levels: [u8; 8] = [0, 36, 73, 109, 146, 182, 219, 255];
+    let mut ticker = Ticker::every(Duration::from_millis(150));
+
+    loop {
+        storeys.set_levels(levels);
+        esp_led.set_level(levels[7]);
+        levels.rotate_right(1);
+        ticker.next().await;
+    }
+}
@@ -7,21 +7,42 @@
 )]
 
 use embassy_executor::Spawner;
-use embassy_futures::select::{select, Either};
 use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
 use embassy_sync::signal::Signal;
 use embassy_time::{Duration, Timer};
 use esp_backtrace as _;
 
 use esp_hal::gpio::Input;
-use hakkaa::board::Board;
+use hakkaa::animation::Step;
+use hakkaa::board::{Board, Led};
+use hakkaa::diag::LedStatus;
+use hakkaa::frame::Frame;
 use hakkaa::led::Storeys;
-use hakkaa::switch::LowActiveSwitch;
+use hakkaa::service::{self, LedChannel, LedHandle, LedService};
+use hakkaa::status::{self, StatusHandle, StatusLed, StatusSignal};
+use hakkaa::switch::Unconnected;
 
 extern crate alloc;
 
 type ButtonSignal = Signal<CriticalSectionRawMutex, ()>;
 
+const STEP: Duration = Duration::from_millis(500);
+
+/// Cycles through the storey LEDs one at a time.
+static CYCLE: [Step; 8] = [
+    Step::new(Frame::D1, STEP),
+    Step::new(Frame::D2, STEP),
+    Step::new(Frame::D3, STEP),
+    Step::new(Frame::D4, STEP),
+    Step::new(Frame::D5, STEP),
+    Step::new(Frame::D6, STEP),
+    Step::new(Frame::D7, STEP),
+    Step::new(Frame::D8, STEP),
+];
+
+/// Blinks all storey LEDs.
+static BLINK: [Step; 2] = [Step::new(Frame::FULL, STEP), Step::new(Frame::EMPTY, STEP)];
+
 // This creates a default app-descriptor required by the esp-idf bootloader.
 // For more information see: <https://docs.espressif.com/projects/esp-idf/en/stable/esp32/api-reference/system/app_image_format.html#application-description>
 esp_bootloader_esp_idf::esp_app_desc!();
@@ -46,79 +67,132 @@ async fn wait_for_button<'a>(button: &mut Input<'a>) {
     button.wait_for_high().await;
 }
 
-/// Waits for `n` presses of `button`.
-async fn wait_for_button_n_times<'a>(button: &mut Input<'a>, n: usize) {
+/// Waits for `n` presses of `button`. Each press is acknowledged by briefly flashing the storey
+/// LEDs.
+async fn wait_for_button_n_times<'a>(button: &mut Input<'a>, n: usize, leds: LedHandle<'_>) {
     for _ in 0..n {
         wait_for_button(button).await;
+        leds.flash(Frame::FULL, Duration::from_millis(100)).await;
     }
 }
 
 /// Task waiting for three times an input on `button` and signalling this event through `signal`.
 #[embassy_executor::task(pool_size = 2)]
-async fn button_task(mut button: Input<'static>, signal: &'static ButtonSignal) {
+async fn button_task(
+    mut button: Input<'static>,
+    signal: &'static ButtonSignal,
+    leds: LedHandle<'static>,
+) {
     loop {
-        wait_for_button_n_times(&mut button, 3).await;
+        wait_for_button_n_times(&mut button, 3, leds).await;
         signal.signal(());
     }
 }
 
+/// Checks the storey LEDs electrically. This spots missing LEDs and solder bridges even before
+/// looking at them. The first faulty LED is reported by blinking its number as error code on the
+/// status LED and its number is returned.
+async fn check_storeys(storeys: &mut Storeys<Led<'_>>, status: StatusHandle<'_>) -> Option<usize> {
+    log::info!("Checking storey LEDs.");
+    status.busy();
+    let mut faulty = None;
+    for (n, led_status) in storeys.diagnose().await.iter().enumerate() {
+        match led_status {
+            LedStatus::Present => log::info!("D{}: {:?}", n + 1, led_status),
+            _ => {
+                log::warn!("D{}: {:?}, check its solder joints", n + 1, led_status);
+                faulty = faulty.or(Some(n + 1));
+            }
+        }
+    }
+
+    if let Some(n) = faulty {
+        status.error(n as u8);
+    }
+    faulty
+}
+
 /// Task performing the board EOL test by orchestrating LED patterns and checking button inputs.
+/// The LEDs are shared with the button tasks through the LED service. The error code of the faulty
+/// LED found by [`check_storeys`] keeps showing on the status LED until the end.
 #[embassy_executor::task]
 async fn eol_task(
-    mut storeys: Storeys<'static>,
     first_button: &'static ButtonSignal,
     second_button: &'static ButtonSignal,
-    mut finished_led: LowActiveSwitch<'static>,
+    leds: LedHandle<'static>,
+    status: StatusHandle<'static>,
+    faulty: Option<usize>,
 ) {
-    let step = Duration::from_millis(500);
-
     // Cycle LEDs while waiting for button presses. This should be the most distinguishable action
     // giving the user all the time need for checking the storey LEDs.
     log::info!(
         "Cycling LEDs. Check that each LED lights up. If they do, press button SW1 three times."
     );
     first_button.reset();
-    match select(storeys.cycle(step), first_button.wait()).await {
-        Either::First(_) => log::debug!("cycle done"),
-        Either::Second(_) => log::debug!("cycle timeout"),
+    leds.play_forever(&CYCLE).await;
+    if faulty.is_none() {
+        status.attention();
     }
+    first_button.wait().await;
 
     // Blink all LEDs while waiting for input from the shake sensor.
     log::info!(
         "Blinking all LEDs. Shake the PCB three times back and forth along the shake sensor axis."
     );
     second_button.reset();
-    match select(storeys.blink(step), second_button.wait()).await {
-        Either::First(_) => log::debug!("blink done"),
-        Either::Second(_) => log::debug!("blink timeout"),
+    leds.play_forever(&BLINK).await;
+    second_button.wait().await;
+
+    // Done. Light up all storey LEDs and let the blue LED on the ESP board show a heartbeat, unless
+    // it is showing the error code of a faulty LED.
+    leds.set_frame(Frame::FULL).await;
+    match faulty {
+        Some(n) => log::error!("EOL test failed. Check the solder joints of D{n} and try again."),
+        None => {
+            status.heartbeat();
+            log::info!("Congratulations! EOL test passed. You may start writing firmware now.");
+        }
     }
-
-    // Done. Light up all storey LEDs and additionally the blue LED on the ESP board.
-    storeys.all_on();
-    finished_led.switch_on();
-
-    log::info!("Congratulations! EOL test passed. You may start writing firmware now.");
     log::info!("Press Ctrl + C to exit.");
 }
 
 static SW1_SIGNAL: ButtonSignal = ButtonSignal::new();
 static U2_SIGNAL: ButtonSignal = ButtonSignal::new();
+static LEDS: LedChannel = LedChannel::new();
+static STATUS: StatusSignal = StatusSignal::new();
 
 #[esp_hal_embassy::main]
 async fn main(spawner: Spawner) -> ! {
     let board = Board::init();
 
-    let storeys = Storeys::new(board.storey_leds);
+    let mut storeys = Storeys::new(board.storey_leds);
+
+    // The blue LED on the ESP board reports the progress of the test.
+    let status_led = StatusLed::new(board.esp_led, &STATUS);
+    let status = status_led.handle();
+    spawner.spawn(status::status_task(status_led)).unwrap();
 
     log::info!("Starting end-of-line (EOL) test.");
+    let faulty = check_storeys(&mut storeys, status).await;
+
+    // Hand the storey LEDs over to the LED service. All other tasks control them through handles.
+    // The blue LED is taken by the status LED already.
+    let service = LedService::new(storeys, Unconnected::new(), &LEDS);
+    let leds = service.handle();
+    spawner.spawn(service::storey_task(service)).unwrap();
+
     // Spawn a debouncing and counting task for each "button". Each triplet of "presses" will
     // generate as signal which is later checked by the EOL task.
-    spawner.spawn(button_task(board.sw1, &SW1_SIGNAL)).unwrap();
-    spawner.spawn(button_task(board.u2, &U2_SIGNAL)).unwrap();
+    spawner
+        .spawn(button_task(board.sw1, &SW1_SIGNAL, leds))
+        .unwrap();
+    spawner
+        .spawn(button_task(board.u2, &U2_SIGNAL, leds))
+        .unwrap();
     // Finally spawn the EOL task showing different storey LED patterns for user inspection of LEDs
     // and as a prompt for pressing SW1 or shaking the board for checking the shake sensor U2.
     spawner
-        .spawn(eol_task(storeys, &SW1_SIGNAL, &U2_SIGNAL, board.esp_led))
+        .spawn(eol_task(&SW1_SIGNAL, &U2_SIGNAL, leds, status, faulty))
         .unwrap();
 
     // Keep the main task running forever.
@@ -14,8 +14,11 @@ use embassy_sync::signal::Signal;
 use embassy_time::{Duration, Instant, Timer};
 use esp_backtrace as _;
 use esp_hal::gpio::Input;
-use hakkaa::board::Board;
+use hakkaa::assets::SMILE;
+use hakkaa::board::{Board, Led};
+use hakkaa::frame::Frame;
 use hakkaa::led::Storeys;
+use hakkaa::mapping::{Mapping, Orientation};
 use heapless::HistoryBuffer;
 
 type DurationSignal = Signal<CriticalSectionRawMutex, Duration>;
@@ -24,37 +27,9 @@ type DurationSignal = Signal<CriticalSectionRawMutex, Duration>;
 // For more information see: <https://docs.espressif.com/projects/esp-idf/en/stable/esp32/api-reference/system/app_image_format.html#application-description>
 esp_bootloader_esp_idf::esp_app_desc!();
 
-// A symmetrical smile pattern. Every entry represents a row. Squint your eyes and have a look from
-// the right side: :)
-#[rustfmt::skip]
-static SMILE_PATTERN: [u8; 26] = [
-    0b00000000,
-    0b00000000,
-    0b00000000,
-    0b00000000,
-    0b11000000,
-    0b11000000,
-    0b00010000,
-    0b00001000,
-    0b00000100,
-    0b00000010,
-    0b00000010,
-    0b00000001,
-    0b00000001,
-    0b00000001,
-    0b00000001,
-    0b00000010,
-    0b00000010,
-    0b00000100,
-    0b00001000,
-    0b00010000,
-    0b11000000,
-    0b11000000,
-    0b00000000,
-    0b00000000,
-    0b00000000,
-    0b00000000,
-];
+// How the board is held while shaking it. Change this for showing the pattern the right way
+// round when holding the board upside down or looking at it from the back.
+const ORIENTATION: Orientation = Orientation::Upright;
 
 async fn delay(duration: Duration) {
     Timer::after(duration).await;
@@ -100,8 +75,8 @@ async fn shake_period(mut sensor: Input<'static>, signal: &'static DurationSigna
 
 /// Display the pattern two times for shaking the board back and forth.
 async fn display_pattern_back_and_forth<'a>(
-    storeys: &mut Storeys<'a>,
-    pattern: &[u8],
+    storeys: &mut Storeys<Led<'a>>,
+    pattern: &[Frame],
     period: Duration,
 ) {
     // Record when this pattern as a reference for timing the display of individual rows.
@@ -109,23 +84,23 @@ async fn display_pattern_back_and_forth<'a>(
 
     // Compute the duration of a single row.
     let half_period = period / 2;
-    let row_duration = half_period / SMILE_PATTERN.len().try_into().unwrap();
+    let row_duration = half_period / pattern.len().try_into().unwrap();
 
     // Display the pattern two times: back and forth. So we are iterating over the rows of the
-    // pattern on the way forward and one more time over the reversed pattern. Wrapping this into
-    // an enumeration gives us a continuous index for each row which we can use to compute the
-    // timing for displaying the actual row.
-    for (index, row) in pattern.iter().chain(pattern.iter().rev()).enumerate() {
-        // Update the storey LEDs for the current row.
-        storeys.set_pattern(*row);
-
-        // Delay for the remaining time for this row.
-        let row: u32 = index.try_into().unwrap();
-        let row_start = pattern_start + row * row_duration;
-        let in_row = Instant::now().duration_since(row_start);
-        let remaining = row_duration - in_row;
-        delay(remaining).await;
+    // pattern on the way forward and one more time over the reversed pattern. The orientation of
+    // the board determines which end of the pattern comes first.
+    let rows = ORIENTATION.columns(pattern.iter());
+    let mut row_start = pattern_start;
+    for row in rows.clone().chain(rows.rev()) {
+        // Prepare the row off-screen and show it right when its time has come. This keeps the
+        // timing independent from how long preparing a row takes.
+        storeys.set_back_buffer(*row);
+        storeys.commit_at(row_start).await;
+        row_start += row_duration;
     }
+
+    // Show the last row for its full duration too.
+    Timer::at(row_start).await;
 }
 
 static SHAKE_PERIOD_SIGNAL: DurationSignal = DurationSignal::new();
@@ -141,7 +116,10 @@ async fn main(spawner: Spawner) -> ! {
     // cross-checking the pattern.
     let initial_period = Duration::from_secs(5);
     let mut period = initial_period;
-    let mut storeys = Storeys::new(board.storey_leds);
+    // Switch all LEDs of a row at once for crisp edges.
+    let mut storeys = Storeys::new(board.storey_leds)
+        .with_mapping(ORIENTATION.mapping(Mapping::IDENTITY))
+        .with_port(board.storey_port);
 
     // Spawn a task for concurrently determining the shake period.
     spawner
@@ -150,8 +128,9 @@ async fn main(spawner: Spawner) -> ! {
 
     loop {
         // Create the futures for cycling the pattern and waiting for the next shake signal. They
-        // well be executed concurrently when awaiting them with select below.
-        let pattern_future = display_pattern_back_and_forth(&mut storeys, &SMILE_PATTERN, period);
+        // well be executed concurrently when awaiting them with select below. The smile comes from
+        // the image `assets/smile.pbm`, converted at build time.
+        let pattern_future = display_pattern_back_and_forth(&mut storeys, &SMILE, period);
         let period_future = SHAKE_PERIOD_SIGNAL.wait();
 
         // Wait for whichever future gets ready first.
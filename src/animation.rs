@@ -0,0 +1,383 @@
+//! Animations made of timed frames.
+//!
+//! An [`Animation`] is an iterator of [`Step`]s, each being a [`Frame`] and the duration for
+//! showing it. Any such iterator is an animation, so the usual iterator adapters like
+//! [`Iterator::map`] and [`Iterator::take`] work as well as the combinators from [`Animation`]:
+//!
+//! ```rust
+//! use embassy_time::Duration;
+//! use hakkaa::animation::{self, Animation};
+//!
+//! let step = Duration::from_millis(100);
+//! let animation = animation::cycle::<8>(step)
+//!     .ping_pong()
+//!     .then(animation::blink(step).repeat(3))
+//!     .speed(2.0);
+//!
+//! assert_eq!(animation.total_duration(), Duration::from_millis(1_000));
+//! ```
+//!
+//! Animations are played on the storey LEDs with [`crate::led::Storeys::play`]. Animations with
+//! their own duration for each frame are easiest to write down as [`Keyframes`].
+
+use core::iter::{Chain, Cycle, Rev};
+
+use embassy_time::Duration;
+
+use crate::frame::Frame;
+
+/// A frame of an [`Animation`] together with the duration for showing it.
+///
+/// Frames are usually [`Frame`]s switching LEDs on and off. Animations for dimmable LEDs use arrays
+/// of brightness [`Level`](crate::gamma::Level)s instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step<F = Frame> {
+    /// The frame to show.
+    pub frame: F,
+    /// The duration for showing the frame.
+    pub duration: Duration,
+}
+
+impl<F> Step<F> {
+    pub const fn new(frame: F, duration: Duration) -> Self {
+        Self { frame, duration }
+    }
+}
+
+/// A sequence of timed frames.
+///
+/// This is implemented for all iterators over [`Step`]s and provides combinators for building
+/// animations from simpler ones.
+pub trait Animation<F = Frame>: Iterator<Item = Step<F>> + Sized {
+    /// Plays `next` after this animation.
+    fn then<A: Animation<F>>(self, next: A) -> Chain<Self, A> {
+        self.chain(next)
+    }
+
+    /// Plays this animation `times` times in a row.
+    fn repeat(self, times: usize) -> Repeat<Self>
+    where
+        Self: Clone,
+    {
+        Repeat {
+            original: self.clone(),
+            current: self,
+            remaining: times,
+        }
+    }
+
+    /// Plays this animation over and over again.
+    fn repeat_forever(self) -> Cycle<Self>
+    where
+        Self: Clone,
+    {
+        self.cycle()
+    }
+
+    /// Plays this animation backwards.
+    fn reverse(self) -> Rev<Self>
+    where
+        Self: DoubleEndedIterator,
+    {
+        self.rev()
+    }
+
+    /// Plays this animation forwards and then backwards. The first and the last frame are not
+    /// repeated at the turning points, so repeating the result plays smoothly back and forth.
+    fn ping_pong(self) -> PingPong<Self, F>
+    where
+        Self: Clone + DoubleEndedIterator,
+    {
+        PingPong {
+            backward: self.clone().rev(),
+            forward: self,
+            turned: false,
+            pending: None,
+        }
+    }
+
+    /// Stops this animation after `duration`. The step running at this point gets cut short.
+    fn limit(self, duration: Duration) -> Limit<Self> {
+        Limit {
+            inner: self,
+            remaining: duration,
+        }
+    }
+
+    /// Plays this animation `factor` times as fast. Factors below 1.0 slow it down. Steps last at
+    /// least one tick of the time driver unless they had no duration to begin with.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `factor` is not a positive, finite number.
+    fn speed(self, factor: f32) -> Speed<Self> {
+        assert!(
+            factor > 0.0 && factor.is_finite(),
+            "the speed factor needs to be positive and finite"
+        );
+        Speed {
+            inner: self,
+            factor: f64::from(factor),
+        }
+    }
+
+    /// Returns the time it takes to play this animation. This never returns for endless
+    /// animations.
+    fn total_duration(self) -> Duration {
+        self.fold(Duration::MIN, |total, step| total + step.duration)
+    }
+}
+
+impl<I: Iterator<Item = Step<F>>, F> Animation<F> for I {}
+
+/// Animation created by [`Animation::repeat`].
+#[derive(Clone, Debug)]
+pub struct Repeat<A> {
+    original: A,
+    current: A,
+    remaining: usize,
+}
+
+impl<A: Iterator<Item = Step<F>> + Clone, F> Iterator for Repeat<A> {
+    type Item = Step<F>;
+
+    fn next(&mut self) -> Option<Step<F>> {
+        while self.remaining > 0 {
+            if let Some(step) = self.current.next() {
+                return Some(step);
+            }
+            self.remaining -= 1;
+            self.current = self.original.clone();
+        }
+        None
+    }
+}
+
+/// Animation created by [`Animation::ping_pong`].
+#[derive(Clone, Debug)]
+pub struct PingPong<A, F = Frame> {
+    forward: A,
+    backward: Rev<A>,
+    turned: bool,
+    pending: Option<Step<F>>,
+}
+
+impl<A: DoubleEndedIterator<Item = Step<F>>, F> Iterator for PingPong<A, F> {
+    type Item = Step<F>;
+
+    fn next(&mut self) -> Option<Step<F>> {
+        if let Some(step) = self.forward.next() {
+            return Some(step);
+        }
+        if !self.turned {
+            // The last frame has just been shown on the way forward.
+            self.backward.next();
+            self.pending = self.backward.next();
+            self.turned = true;
+        }
+
+        // Leave out the first frame on the way back. It gets shown next when playing the
+        // animation again.
+        let step = self.pending.take()?;
+        self.pending = self.backward.next();
+        self.pending.as_ref().map(|_| step)
+    }
+}
+
+/// Animation created by [`Animation::limit`].
+#[derive(Clone, Debug)]
+pub struct Limit<A> {
+    inner: A,
+    remaining: Duration,
+}
+
+impl<A: Iterator<Item = Step<F>>, F> Iterator for Limit<A> {
+    type Item = Step<F>;
+
+    fn next(&mut self) -> Option<Step<F>> {
+        if self.remaining == Duration::MIN {
+            return None;
+        }
+        let mut step = self.inner.next()?;
+        step.duration = step.duration.min(self.remaining);
+        self.remaining -= step.duration;
+        Some(step)
+    }
+}
+
+/// Animation created by [`Animation::speed`].
+#[derive(Clone, Debug)]
+pub struct Speed<A> {
+    inner: A,
+    factor: f64,
+}
+
+impl<A: Iterator<Item = Step<F>>, F> Iterator for Speed<A> {
+    type Item = Step<F>;
+
+    fn next(&mut self) -> Option<Step<F>> {
+        let mut step = self.inner.next()?;
+        let ticks = (step.duration.as_ticks() as f64 / self.factor) as u64;
+        // Steps rounded down to nothing would never let any time pass when repeated.
+        step.duration = match ticks {
+            0 if step.duration > Duration::MIN => Duration::from_ticks(1),
+            ticks => Duration::from_ticks(ticks),
+        };
+        Some(step)
+    }
+}
+
+/// How [`Keyframes`] go on after their last frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LoopMode {
+    /// Play the frames once.
+    #[default]
+    Once,
+    /// Play the frames the given number of times in a row.
+    Times(usize),
+    /// Start over after the last frame, forever.
+    Forever,
+    /// Play the frames forwards and backwards, forever. The first and the last frame are not
+    /// repeated at the turning points.
+    PingPong,
+}
+
+/// An animation stored as static keyframes, each with its own duration.
+///
+/// Keyframes are built at compile time, so they take no RAM:
+///
+/// ```rust
+/// use embassy_time::Duration;
+/// use hakkaa::animation::{Animation, Keyframes, LoopMode, Step};
+/// use hakkaa::frame::Frame;
+///
+/// static BEACON: Keyframes = Keyframes::new(&[
+///     Step::new(Frame::FULL, Duration::from_millis(50)),
+///     Step::new(Frame::EMPTY, Duration::from_millis(950)),
+/// ])
+/// .with_loop(LoopMode::Times(3));
+///
+/// assert_eq!(BEACON.steps().total_duration(), Duration::from_secs(3));
+/// ```
+///
+/// Play them on the storey LEDs with [`crate::led::Storeys::play_keyframes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Keyframes<F: 'static = Frame> {
+    frames: &'static [Step<F>],
+    mode: LoopMode,
+}
+
+impl<F> Keyframes<F> {
+    /// Creates keyframes played once.
+    pub const fn new(frames: &'static [Step<F>]) -> Self {
+        Self {
+            frames,
+            mode: LoopMode::Once,
+        }
+    }
+
+    /// Sets how to go on after the last frame.
+    pub const fn with_loop(mut self, mode: LoopMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Returns the keyframes.
+    pub const fn frames(&self) -> &'static [Step<F>] {
+        self.frames
+    }
+
+    /// Returns how to go on after the last frame.
+    pub const fn mode(&self) -> LoopMode {
+        self.mode
+    }
+
+    /// Returns the steps for playing the keyframes according to their loop mode.
+    ///
+    /// Keyframes without any duration get played at most once, whatever their loop mode.
+    /// Repeating them would never let any time pass and keep the executor busy forever.
+    pub fn steps(&self) -> KeyframeSteps<F> {
+        let timeless = self
+            .frames
+            .iter()
+            .all(|step| step.duration == Duration::MIN);
+        let mode = match self.mode {
+            LoopMode::Times(times) if timeless => LoopMode::Times(times.min(1)),
+            _ if timeless => LoopMode::Once,
+            mode => mode,
+        };
+        KeyframeSteps {
+            frames: self.frames,
+            mode,
+            position: 0,
+        }
+    }
+}
+
+/// Animation created by [`Keyframes::steps`].
+#[derive(Clone, Debug)]
+pub struct KeyframeSteps<F: 'static = Frame> {
+    frames: &'static [Step<F>],
+    mode: LoopMode,
+    /// The position within the sequence of frames. This wraps around for endless loops.
+    position: usize,
+}
+
+impl<F: Copy> Iterator for KeyframeSteps<F> {
+    type Item = Step<F>;
+
+    fn next(&mut self) -> Option<Step<F>> {
+        let len = self.frames.len();
+        if len == 0 {
+            return None;
+        }
+
+        let index = self.position;
+        match self.mode {
+            LoopMode::Once | LoopMode::Times(_) => {
+                let times = match self.mode {
+                    LoopMode::Times(times) => times,
+                    _ => 1,
+                };
+                if index >= len.saturating_mul(times) {
+                    return None;
+                }
+                self.position += 1;
+                Some(self.frames[index % len])
+            }
+            LoopMode::Forever => {
+                self.position = (index + 1) % len;
+                Some(self.frames[index])
+            }
+            LoopMode::PingPong => {
+                let period = (2 * len - 2).max(1);
+                self.position = (index + 1) % period;
+                Some(self.frames[if index < len { index } else { period - index }])
+            }
+        }
+    }
+}
+
+/// Shows `frames` one after another, each for `step`.
+pub fn frames<const N: usize>(
+    frames: &[Frame<N>],
+    step: Duration,
+) -> impl Animation<Frame<N>> + Clone + DoubleEndedIterator + ExactSizeIterator + '_ {
+    frames.iter().map(move |frame| Step::new(*frame, step))
+}
+
+/// Switches on one LED after another, each for `step`.
+pub fn cycle<const N: usize>(
+    step: Duration,
+) -> impl Animation<Frame<N>> + Clone + DoubleEndedIterator + ExactSizeIterator {
+    (0..N).map(move |n| Step::new(Frame::single(n), step))
+}
+
+/// Switches all LEDs on for `step` and off for `step`.
+pub fn blink<const N: usize>(
+    step: Duration,
+) -> impl Animation<Frame<N>> + Clone + DoubleEndedIterator + ExactSizeIterator {
+    [Frame::FULL, Frame::EMPTY]
+        .into_iter()
+        .map(move |frame| Step::new(frame, step))
+}
@@ -0,0 +1,27 @@
+//! Patterns converted from the images in the `assets/` directory at build time.
+//!
+//! Draw images for persistence of vision in your favourite image editor and save them as PBM or PNG
+//! in `assets/`. The build script turns each of them into a static array with one
+//! [`Frame`](crate::frame::Frame) per column of the image, named after the file. So
+//! `assets/smile.pbm` becomes [`SMILE`]:
+//!
+//! ```rust
+//! use hakkaa::assets::SMILE;
+//! use hakkaa::frame::Frame;
+//!
+//! assert_eq!(SMILE.len(), 26);
+//! assert_eq!(SMILE[12], Frame::D1);
+//! ```
+//!
+//! The images are converted like this:
+//!
+//! - Each row of the image is a storey LED, with D1 at the bottom. Images which are not eight
+//!   pixels high get scaled to eight rows.
+//! - Dark pixels are LEDs switched on. Light and transparent ones are switched off.
+//! - Shades of gray are thresholded at half. With the option `dither` in the file name, like in
+//!   `photo.dither.png`, they get dithered instead.
+//!
+//! PBM images are plain text in their plain variant and a good choice for drawing small images in
+//! a text editor. See `build/assets.rs` for the details of the conversion.
+
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
@@ -1,22 +1,45 @@
 //! Board support for the Hakkaa board.
 
 use esp_hal::clock::CpuClock;
-use esp_hal::gpio::{DriveMode, Input, InputConfig, Level, Output, OutputConfig, Pull};
+use esp_hal::gpio::{DriveMode, Flex, Input, InputConfig, Level, OutputConfig, Pin, Pull};
+use esp_hal::peripherals::{GPIO, GPIO_SD, LEDC};
 use esp_hal::timer::systimer::SystemTimer;
 
+use crate::diag::{self, ProbePin};
 use crate::led::STOREY_LEDS;
-use crate::switch::LowActiveSwitch;
+use crate::switch::{LowActiveSwitch, Port};
+
+/// The switch type for all the LEDs on the board. They are all connected to open-drain outputs
+/// which are switched on by pulling them low. The pins stay readable for diagnostics.
+pub type Led<'a> = LowActiveSwitch<Flex<'a>>;
 
 /// Hakkaa board resources.
 pub struct Board<'a> {
     /// The outputs for driving the storey LEDs _D1_ to _D8_ on the main board.
-    pub storey_leds: [LowActiveSwitch<'a>; STOREY_LEDS],
+    pub storey_leds: [Led<'a>; STOREY_LEDS],
+    /// The port for switching all storey LEDs at once with [`crate::led::Storeys::with_port`].
+    ///
+    /// It writes the outputs of [`Board::storey_leds`] behind their backs. Only use it together
+    /// with them, for [`crate::led::Storeys`] built from them. Otherwise their state gets out of
+    /// sync with the actual outputs.
+    ///
+    /// In the release build, the port writes the clear and set registers with two consecutive
+    /// store instructions. So LEDs switching in the same direction change at the very same clock
+    /// cycle and LEDs switching in opposite directions one store apart. Without the port, the LEDs
+    /// get written one after another with the driver code for each of them in between. The skew
+    /// between D1 (GPIO3) and D8 (GPIO5) has not been measured with a logic analyzer yet, so there
+    /// are no numbers for it.
+    pub storey_port: Port<STOREY_LEDS>,
     /// The output for driving the blue LED on the ESP32-C3 board _U1_.
-    pub esp_led: LowActiveSwitch<'a>,
+    pub esp_led: Led<'a>,
     /// The input the push putton _SW1_ on the main board is connected to.
     pub sw1: Input<'a>,
     /// The input the shake sensor _U2_ on the main board is connected to.
     pub u2: Input<'a>,
+    /// The LEDC PWM peripheral for dimming LEDs with [`crate::dim::Dimming`].
+    pub ledc: LEDC<'a>,
+    /// The sigma-delta modulators for dimming LEDs with [`crate::dim::Dimming`].
+    pub gpio_sd: GPIO_SD<'a>,
 }
 
 impl<'a> Board<'a> {
@@ -48,53 +71,22 @@ impl<'a> Board<'a> {
             .with_pull(Pull::None);
         let led_pin_init_level = Level::High;
 
-        let storey_leds = [
-            LowActiveSwitch::new(Output::new(
-                peripherals.GPIO3,
-                led_pin_init_level,
-                led_pin_config,
-            )),
-            LowActiveSwitch::new(Output::new(
-                peripherals.GPIO4,
-                led_pin_init_level,
-                led_pin_config,
-            )),
-            LowActiveSwitch::new(Output::new(
-                peripherals.GPIO21,
-                led_pin_init_level,
-                led_pin_config,
-            )),
-            LowActiveSwitch::new(Output::new(
-                peripherals.GPIO20,
-                led_pin_init_level,
-                led_pin_config,
-            )),
-            LowActiveSwitch::new(Output::new(
-                peripherals.GPIO10,
-                led_pin_init_level,
-                led_pin_config,
-            )),
-            LowActiveSwitch::new(Output::new(
-                peripherals.GPIO7,
-                led_pin_init_level,
-                led_pin_config,
-            )),
-            LowActiveSwitch::new(Output::new(
-                peripherals.GPIO6,
-                led_pin_init_level,
-                led_pin_config,
-            )),
-            LowActiveSwitch::new(Output::new(
-                peripherals.GPIO5,
-                led_pin_init_level,
-                led_pin_config,
-            )),
+        let storey_pins = [
+            peripherals.GPIO3.degrade(),
+            peripherals.GPIO4.degrade(),
+            peripherals.GPIO21.degrade(),
+            peripherals.GPIO20.degrade(),
+            peripherals.GPIO10.degrade(),
+            peripherals.GPIO7.degrade(),
+            peripherals.GPIO6.degrade(),
+            peripherals.GPIO5.degrade(),
         ];
-        let esp_led = LowActiveSwitch::new(Output::new(
-            peripherals.GPIO8,
-            led_pin_init_level,
-            led_pin_config,
-        ));
+        let storey_port = Port::low_active(
+            storey_pins.each_ref().map(|pin| 1 << pin.number()),
+            write_outputs,
+        );
+        let storey_leds = storey_pins.map(|pin| led(pin, led_pin_init_level, &led_pin_config));
+        let esp_led = led(peripherals.GPIO8, led_pin_init_level, &led_pin_config);
 
         let switch_pin_config = InputConfig::default().with_pull(Pull::Up);
         let sw1 = Input::new(peripherals.GPIO1, switch_pin_config);
@@ -104,9 +96,46 @@ impl<'a> Board<'a> {
 
         Board {
             storey_leds,
+            storey_port,
             esp_led,
             sw1,
             u2,
+            ledc: peripherals.LEDC,
+            gpio_sd: peripherals.GPIO_SD,
         }
     }
 }
+
+/// Sets up an LED on the given pin as open-drain output.
+fn led<'a>(pin: impl Pin + 'a, level: Level, config: &OutputConfig) -> Led<'a> {
+    let mut pin = Flex::new(pin);
+    pin.set_level(level);
+    pin.apply_output_config(config);
+    pin.set_output_enable(true);
+    LowActiveSwitch::new(pin)
+}
+
+/// Sets and clears GPIO outputs with a single write to the set and clear register each. All GPIOs
+/// of the ESP32-C3 are in the same bank.
+fn write_outputs(set: u32, clear: u32) {
+    let gpio = GPIO::regs();
+    // The registers only affect the outputs with their bit set and any bits are fine to write.
+    gpio.out_w1tc().write(|w| unsafe { w.bits(clear) });
+    gpio.out_w1ts().write(|w| unsafe { w.bits(set) });
+}
+
+impl ProbePin for Flex<'_> {
+    fn set_pull(&mut self, pull: Option<diag::Pull>) {
+        let pull = match pull {
+            Some(diag::Pull::Up) => Pull::Up,
+            Some(diag::Pull::Down) => Pull::Down,
+            None => Pull::None,
+        };
+        self.apply_input_config(&InputConfig::default().with_pull(pull));
+        self.set_input_enable(true);
+    }
+
+    fn is_high(&mut self) -> bool {
+        Flex::is_high(self)
+    }
+}
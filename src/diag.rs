@@ -0,0 +1,87 @@
+//! Diagnostics for finding missing or badly soldered LEDs.
+//!
+//! All LEDs are connected from the supply through a current limiting resistor to an open-drain
+//! GPIO. Releasing a GPIO and reading it back with its internal pull resistors reveals how it is
+//! connected:
+//!
+//! * With the pull-down resistor, a soldered LED conducts a tiny current from the supply and lifts
+//!   the pin towards the supply voltage. Without an LED, nothing lifts the pin and it reads low.
+//! * With the pull-up resistor, the pin reads high unless something shorts it to ground.
+//! * Pulling one LED pin low while reading the others with their pull-up resistors reveals solder
+//!   bridges between them.
+//!
+//! The ESP32-C3 guarantees reading a pin as low below a quarter of the supply voltage and as high
+//! above three quarters of it. A missing LED leaves the pin at ground with the pull-down resistor,
+//! so it safely reads low. A soldered LED lifts the pin only to the supply voltage minus its
+//! forward voltage at microampere currents. This is about 1.8 V for red LEDs on a 3.3 V supply,
+//! which is in between the guaranteed levels: Whether it reads high depends on the actual switching
+//! point of the input, which is typically close to half the supply voltage. LEDs with a higher
+//! forward voltage like blue and white ones stay below it.
+//!
+//! So [`LedStatus::Present`] and [`LedStatus::Shorted`] are reliable, but [`LedStatus::Open`] only
+//! tells that the LED did not lift its pin. This has not been validated on hardware across LED
+//! colors and chips yet. Measuring the voltage of a pin with a soldered LED and the pull-down
+//! resistor enabled tells how much margin there is to the switching point of the input.
+//!
+//! The readings are taken by [`crate::switch::LowActiveSwitch::diagnose`] and
+//! [`crate::led::Storeys::diagnose`]. Turning them into an [`LedStatus`] with [`classify`] does not
+//! depend on the hardware.
+
+use embassy_time::Duration;
+
+/// The time to wait for the level of a pin to settle after changing its pull resistors.
+pub(crate) const SETTLING_TIME: Duration = Duration::from_micros(100);
+
+/// The pull resistors of a GPIO.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pull {
+    /// The pull-up resistor to the supply.
+    Up,
+    /// The pull-down resistor to ground.
+    Down,
+}
+
+/// Pins which can be read back with their pull resistors while being used as an output.
+pub trait ProbePin {
+    /// Enables the given pull resistor, or none at all, and enables reading the pin.
+    fn set_pull(&mut self, pull: Option<Pull>);
+
+    /// Returns whether the pin reads high.
+    fn is_high(&mut self) -> bool;
+}
+
+/// The readings taken from the GPIO of a released LED.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Readings {
+    /// The pin read high with the pull-up resistor enabled.
+    pub pulled_up: bool,
+    /// The pin read high with the pull-down resistor enabled.
+    pub pulled_down: bool,
+    /// The pin is bridged to another LED pin: Pulling one of them low pulls down the other one as
+    /// well.
+    pub bridged: bool,
+}
+
+/// The status of an LED as determined from its [`Readings`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LedStatus {
+    /// The LED is present and connected.
+    Present,
+    /// The LED did not lift its GPIO with the pull-down resistor enabled. The LED is missing or
+    /// one of its joints or the joints of its resistor is bad. An LED with a high forward voltage
+    /// might be reported as open as well, see the [module documentation](self).
+    Open,
+    /// The GPIO is shorted to ground or bridged to another LED.
+    Shorted,
+}
+
+/// Classifies an LED from the readings taken from its GPIO.
+pub fn classify(readings: Readings) -> LedStatus {
+    if !readings.pulled_up || readings.bridged {
+        LedStatus::Shorted
+    } else if readings.pulled_down {
+        LedStatus::Present
+    } else {
+        LedStatus::Open
+    }
+}
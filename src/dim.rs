@@ -0,0 +1,413 @@
+//! Dimming LEDs with the help of the PWM peripherals of the ESP32-C3.
+//!
+//! The ESP32-C3 comes with six LEDC PWM channels and four sigma-delta modulators. Both generate
+//! their output signals in hardware and dim LEDs without costing any CPU time. [`Dimming::new`]
+//! assigns these channels to as many LEDs as possible and drives the remaining ones with the
+//! [`SoftPwm`]. All of them are handed out as [`DimmableSwitch`] for dimming them through the same
+//! [`Dimmable`] interface.
+//!
+//! The LEDs on the Hakkaa board are switched on by pulling their GPIO low. So the hardware outputs
+//! are inverted for getting the brightness of an LED proportional to the duty cycle.
+
+use core::fmt;
+
+use embassy_time::Duration;
+use esp_hal::gpio::interconnect::OutputSignal;
+use esp_hal::gpio::{self, Flex};
+use esp_hal::ledc::channel::{self, ChannelHW, ChannelIFace};
+use esp_hal::ledc::timer::{self, TimerIFace};
+use esp_hal::ledc::{LSGlobalClkSource, Ledc, LowSpeed};
+use esp_hal::peripherals::{GPIO_SD, LEDC};
+use esp_hal::time::Rate;
+use heapless::Vec;
+use static_cell::StaticCell;
+
+use crate::board::Led;
+use crate::gamma::{GammaTable, Level, GAMMA};
+use crate::pwm::{Duties, PwmChannel, SoftPwm, FULL_DUTY};
+use crate::switch::{Dimmable, Switch};
+
+/// The LEDC channels available for dimming LEDs.
+const LEDC_CHANNELS: [channel::Number; 6] = [
+    channel::Number::Channel0,
+    channel::Number::Channel1,
+    channel::Number::Channel2,
+    channel::Number::Channel3,
+    channel::Number::Channel4,
+    channel::Number::Channel5,
+];
+
+/// The output signals of the sigma-delta modulators available for dimming LEDs.
+const SIGMA_DELTA_SIGNALS: [gpio::OutputSignal; 4] = [
+    gpio::OutputSignal::GPIO_SD0,
+    gpio::OutputSignal::GPIO_SD1,
+    gpio::OutputSignal::GPIO_SD2,
+    gpio::OutputSignal::GPIO_SD3,
+];
+
+/// The PWM frequency of the LEDC channels. This is way beyond anything visible.
+const LEDC_FREQUENCY: Rate = Rate::from_khz(20);
+
+/// The resolution of the LEDC duty cycle in bits. This matches the resolution of
+/// [`Dimmable::set_brightness`].
+const LEDC_DUTY_BITS: u32 = 8;
+
+/// The most PWM cycles the LEDC peripheral can take for a single duty cycle step of a fade.
+const MAX_CYCLES_PER_STEP: u64 = 1023;
+
+/// The prescaler for the 80 MHz input clock of the sigma-delta modulators.
+const SIGMA_DELTA_PRESCALE: u8 = 79;
+
+static LEDC_DRIVER: StaticCell<Ledc<'static>> = StaticCell::new();
+static LEDC_TIMER: StaticCell<timer::Timer<'static, LowSpeed>> = StaticCell::new();
+
+/// An LED dimmed by a LEDC PWM channel.
+pub struct LedcLed<'d> {
+    channel: channel::Channel<'d, LowSpeed>,
+    brightness: u8,
+    gamma: &'d GammaTable,
+}
+
+impl<'d> LedcLed<'d> {
+    fn new(
+        ledc: &Ledc<'d>,
+        timer: &'d timer::Timer<'d, LowSpeed>,
+        number: channel::Number,
+        output: Flex<'d>,
+        gamma: &'d GammaTable,
+    ) -> Self {
+        let output = OutputSignal::from(output).with_output_inverter(true);
+        let mut channel = ledc.channel(number, output);
+        channel
+            .configure(channel::config::Config {
+                timer,
+                duty_pct: 0,
+                pin_config: channel::config::PinConfig::OpenDrain,
+            })
+            .unwrap();
+
+        Self {
+            channel,
+            brightness: 0,
+            gamma,
+        }
+    }
+
+    /// Fades the LED from its current brightness to the perceptual brightness `level` within
+    /// `duration`. The level gets mapped to a duty cycle with the gamma table from
+    /// [`DimmingConfig::with_gamma`].
+    ///
+    /// The fade is performed by the LEDC peripheral in the background, one duty cycle step at a
+    /// time. It takes at most 1023 PWM cycles per step, so long fades over a few steps end early.
+    /// Fades too short for a single PWM cycle per step just set the brightness. The LED reports the
+    /// target brightness right from the start.
+    pub fn start_fade(&mut self, level: impl Into<Level>, duration: Duration) {
+        let brightness = self.gamma.duty(level.into());
+        let (from, to) = (ledc_duty(self.brightness), ledc_duty(brightness));
+        let steps = from.abs_diff(to);
+        let cycles = duration.as_micros() * u64::from(LEDC_FREQUENCY.as_hz()) / 1_000_000;
+        let cycles_per_step = (cycles / u64::from(steps.max(1))).min(MAX_CYCLES_PER_STEP);
+
+        if steps == 0 || cycles_per_step == 0 {
+            self.set_brightness(brightness);
+        } else {
+            // There are at most 256 steps with the duty cycle resolution in use.
+            self.channel.start_duty_fade_hw(
+                from,
+                to > from,
+                steps as u16,
+                cycles_per_step as u16,
+                1,
+            );
+        }
+        self.brightness = brightness;
+    }
+
+    /// Returns whether a fade started with [`LedcLed::start_fade`] is still running.
+    pub fn is_fading(&self) -> bool {
+        self.channel.is_duty_fade_running()
+    }
+}
+
+impl Switch for LedcLed<'_> {
+    fn switch_on(&mut self) {
+        self.set_brightness(FULL_DUTY);
+    }
+
+    fn switch_off(&mut self) {
+        self.set_brightness(0);
+    }
+
+    fn is_on(&self) -> bool {
+        self.brightness > 0
+    }
+}
+
+impl Dimmable for LedcLed<'_> {
+    fn set_brightness(&mut self, brightness: u8) {
+        self.channel.set_duty_hw(ledc_duty(brightness));
+        self.brightness = brightness;
+    }
+
+    fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    fn gamma(&self) -> &GammaTable {
+        self.gamma
+    }
+}
+
+impl fmt::Debug for LedcLed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LedcLed")
+            .field("brightness", &self.brightness)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the LEDC duty cycle for `brightness`.
+fn ledc_duty(brightness: u8) -> u32 {
+    // The full duty cycle is one step beyond the brightness range.
+    match brightness {
+        FULL_DUTY => 1 << LEDC_DUTY_BITS,
+        brightness => u32::from(brightness),
+    }
+}
+
+/// An LED dimmed by a sigma-delta modulator.
+pub struct SigmaDeltaLed<'d> {
+    channel: usize,
+    _output: OutputSignal<'d>,
+    brightness: u8,
+    gamma: &'d GammaTable,
+}
+
+impl<'d> SigmaDeltaLed<'d> {
+    fn new(channel: usize, output: Flex<'d>, gamma: &'d GammaTable) -> Self {
+        let output = OutputSignal::from(output).with_output_inverter(true);
+        SIGMA_DELTA_SIGNALS[channel].connect_to(&output);
+
+        let mut led = Self {
+            channel,
+            _output: output,
+            brightness: 0,
+            gamma,
+        };
+        led.switch_off();
+        led
+    }
+}
+
+impl Switch for SigmaDeltaLed<'_> {
+    fn switch_on(&mut self) {
+        self.set_brightness(FULL_DUTY);
+    }
+
+    fn switch_off(&mut self) {
+        self.set_brightness(0);
+    }
+
+    fn is_on(&self) -> bool {
+        self.brightness > 0
+    }
+}
+
+impl Dimmable for SigmaDeltaLed<'_> {
+    fn set_brightness(&mut self, brightness: u8) {
+        // The modulator takes a signed density from -128 (always low) to 127 (almost always high).
+        let density = (i16::from(brightness) + i16::from(i8::MIN)) as i8;
+        GPIO_SD::regs().sigmadelta(self.channel).write(|w| unsafe {
+            w.in_().bits(density as u8);
+            w.prescale().bits(SIGMA_DELTA_PRESCALE)
+        });
+        self.brightness = brightness;
+    }
+
+    fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    fn gamma(&self) -> &GammaTable {
+        self.gamma
+    }
+}
+
+impl fmt::Debug for SigmaDeltaLed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigmaDeltaLed")
+            .field("channel", &self.channel)
+            .field("brightness", &self.brightness)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An LED dimmed by one of the available backends.
+#[derive(Debug)]
+pub enum DimmableSwitch<'d, const N: usize> {
+    /// Dimmed by a LEDC PWM channel.
+    Ledc(LedcLed<'d>),
+    /// Dimmed by a sigma-delta modulator.
+    SigmaDelta(SigmaDeltaLed<'d>),
+    /// Dimmed by [`SoftPwm`].
+    Software(PwmChannel<'d, N>),
+}
+
+impl<const N: usize> Switch for DimmableSwitch<'_, N> {
+    fn switch_on(&mut self) {
+        self.set_brightness(FULL_DUTY);
+    }
+
+    fn switch_off(&mut self) {
+        self.set_brightness(0);
+    }
+
+    fn is_on(&self) -> bool {
+        self.brightness() > 0
+    }
+}
+
+impl<const N: usize> Dimmable for DimmableSwitch<'_, N> {
+    fn set_brightness(&mut self, brightness: u8) {
+        match self {
+            Self::Ledc(led) => led.set_brightness(brightness),
+            Self::SigmaDelta(led) => led.set_brightness(brightness),
+            Self::Software(led) => led.set_brightness(brightness),
+        }
+    }
+
+    fn brightness(&self) -> u8 {
+        match self {
+            Self::Ledc(led) => led.brightness(),
+            Self::SigmaDelta(led) => led.brightness(),
+            Self::Software(led) => led.brightness(),
+        }
+    }
+
+    fn gamma(&self) -> &GammaTable {
+        match self {
+            Self::Ledc(led) => led.gamma(),
+            Self::SigmaDelta(led) => led.gamma(),
+            Self::Software(led) => led.gamma(),
+        }
+    }
+}
+
+/// Configuration for [`Dimming::new`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DimmingConfig {
+    ledc_channels: usize,
+    sigma_delta_channels: usize,
+    gamma: &'static GammaTable,
+}
+
+impl DimmingConfig {
+    /// Sets the number of LEDC channels to use for dimming, starting from channel 0. Use less than
+    /// all six channels for keeping the remaining ones for other purposes.
+    pub fn with_ledc_channels(mut self, channels: usize) -> Self {
+        self.ledc_channels = channels.min(LEDC_CHANNELS.len());
+        self
+    }
+
+    /// Sets the number of sigma-delta modulators to use for dimming, starting from modulator 0.
+    /// Use less than all four modulators for keeping the remaining ones for other purposes.
+    pub fn with_sigma_delta_channels(mut self, channels: usize) -> Self {
+        self.sigma_delta_channels = channels.min(SIGMA_DELTA_SIGNALS.len());
+        self
+    }
+
+    /// Sets the gamma table for mapping perceptual brightness levels to duty cycles in
+    /// [`Dimmable::set_level`] and [`LedcLed::start_fade`] of all backends. This is [`GAMMA`] by
+    /// default.
+    pub fn with_gamma(mut self, gamma: &'static GammaTable) -> Self {
+        self.gamma = gamma;
+        self
+    }
+}
+
+impl Default for DimmingConfig {
+    fn default() -> Self {
+        Self {
+            ledc_channels: LEDC_CHANNELS.len(),
+            sigma_delta_channels: SIGMA_DELTA_SIGNALS.len(),
+            gamma: &GAMMA,
+        }
+    }
+}
+
+/// LEDs dimmed by the available hardware and [`SoftPwm`] as fallback.
+///
+/// There is no power budget for the LEDs as they are handed out: Each of them gets dimmed on its
+/// own and knows nothing about the others. Build [`crate::led::Storeys`] from them and give it a
+/// [`crate::power::Budget`] for limiting the current they draw together.
+#[derive(Debug)]
+pub struct Dimming<const N: usize> {
+    /// The dimmable LEDs in the order passed to [`Dimming::new`].
+    pub leds: [DimmableSwitch<'static, N>; N],
+    /// The software PWM for the LEDs which did not get a hardware channel. Spawn a task running
+    /// it. There is nothing to run if all LEDs got a hardware channel.
+    pub soft_pwm: Option<SoftPwm<'static, Led<'static>, N>>,
+}
+
+impl<const N: usize> Dimming<N> {
+    /// Sets up dimming for the given LEDs. The first LEDs get LEDC channels, the next ones
+    /// sigma-delta modulators, and the remaining ones get dimmed by software PWM with the duty
+    /// cycles from `duties`.
+    ///
+    /// # Panics
+    ///
+    /// Setting up dimming is designed to be performed only once. Subsequent calls to this function
+    /// will cause a panic.
+    pub fn new(
+        leds: [Led<'static>; N],
+        ledc: LEDC<'static>,
+        _gpio_sd: GPIO_SD<'static>,
+        duties: &'static Duties<N>,
+        config: DimmingConfig,
+    ) -> Self {
+        let ledc = LEDC_DRIVER.init(Ledc::new(ledc));
+        ledc.set_global_slow_clock(LSGlobalClkSource::APBClk);
+        let timer = LEDC_TIMER.init(ledc.timer::<LowSpeed>(timer::Number::Timer0));
+        timer
+            .configure(timer::config::Config {
+                duty: timer::config::Duty::Duty8Bit,
+                clock_source: timer::LSClockSource::APBClk,
+                frequency: LEDC_FREQUENCY,
+            })
+            .unwrap();
+
+        // The sigma-delta modulators come without a driver from esp-hal. Taking their peripheral
+        // makes sure nobody else is using them and we just need to enable their clock.
+        let regs = GPIO_SD::regs();
+        regs.sigmadelta_misc()
+            .modify(|_, w| w.function_clk_en().set_bit());
+        regs.clock_gate().modify(|_, w| w.clk_en().set_bit());
+
+        let mut channels = duties.channels().map(Some);
+        let mut software = Vec::new();
+        let mut leds = leds.into_iter().enumerate();
+
+        let leds = core::array::from_fn(|_| {
+            let (index, led) = leds.next().unwrap();
+            let output = led.free();
+            let sigma_delta = index.wrapping_sub(config.ledc_channels);
+
+            if index < config.ledc_channels {
+                let number = LEDC_CHANNELS[index];
+                DimmableSwitch::Ledc(LedcLed::new(ledc, timer, number, output, config.gamma))
+            } else if sigma_delta < config.sigma_delta_channels {
+                DimmableSwitch::SigmaDelta(SigmaDeltaLed::new(sigma_delta, output, config.gamma))
+            } else {
+                // There are at most as many LEDs for software PWM as LEDs at all.
+                software
+                    .push((index, Led::new(output)))
+                    .unwrap_or_else(|_| unreachable!());
+                DimmableSwitch::Software(channels[index].take().unwrap().with_gamma(config.gamma))
+            }
+        });
+
+        let soft_pwm = (!software.is_empty()).then(|| SoftPwm::new_partial(software, duties));
+
+        Self { leds, soft_pwm }
+    }
+}
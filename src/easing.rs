@@ -0,0 +1,122 @@
+//! Easing curves for shaping the progress of transitions.
+//!
+//! An easing function maps the linear progress of a transition from 0.0 to 1.0 to the progress to
+//! show. Starting slowly and speeding up, or bouncing into the end, makes changes look more
+//! natural than a constant speed. The functions are available on their own and through [`Easing`]
+//! for choosing one at runtime:
+//!
+//! ```rust
+//! use hakkaa::easing::{self, Easing};
+//!
+//! assert_eq!(easing::ease_in(0.5), 0.25);
+//! assert_eq!(Easing::EaseIn.apply(0.5), 0.25);
+//! ```
+//!
+//! All functions start at 0.0 and end at 1.0. Progress outside this range is clamped.
+
+/// The easing curves from this module.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Easing {
+    /// See [`linear`].
+    #[default]
+    Linear,
+    /// See [`ease_in`].
+    EaseIn,
+    /// See [`ease_out`].
+    EaseOut,
+    /// See [`ease_in_out`].
+    EaseInOut,
+    /// See [`cubic_in`].
+    CubicIn,
+    /// See [`cubic_out`].
+    CubicOut,
+    /// See [`cubic_in_out`].
+    CubicInOut,
+    /// See [`bounce_out`].
+    Bounce,
+}
+
+impl Easing {
+    /// Applies this easing curve to the linear progress `t`.
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => linear(t),
+            Self::EaseIn => ease_in(t),
+            Self::EaseOut => ease_out(t),
+            Self::EaseInOut => ease_in_out(t),
+            Self::CubicIn => cubic_in(t),
+            Self::CubicOut => cubic_out(t),
+            Self::CubicInOut => cubic_in_out(t),
+            Self::Bounce => bounce_out(t),
+        }
+    }
+}
+
+/// Constant speed.
+pub fn linear(t: f32) -> f32 {
+    t.clamp(0.0, 1.0)
+}
+
+/// Starts slowly and speeds up, quadratically.
+pub fn ease_in(t: f32) -> f32 {
+    let t = linear(t);
+    t * t
+}
+
+/// Starts fast and slows down, quadratically.
+pub fn ease_out(t: f32) -> f32 {
+    1.0 - ease_in(1.0 - t)
+}
+
+/// Starts slowly, speeds up, and slows down again, quadratically.
+pub fn ease_in_out(t: f32) -> f32 {
+    let t = linear(t);
+    if t < 0.5 {
+        ease_in(2.0 * t) / 2.0
+    } else {
+        0.5 + ease_out(2.0 * t - 1.0) / 2.0
+    }
+}
+
+/// Starts slowly and speeds up, cubically.
+pub fn cubic_in(t: f32) -> f32 {
+    let t = linear(t);
+    t * t * t
+}
+
+/// Starts fast and slows down, cubically.
+pub fn cubic_out(t: f32) -> f32 {
+    1.0 - cubic_in(1.0 - t)
+}
+
+/// Starts slowly, speeds up, and slows down again, cubically.
+pub fn cubic_in_out(t: f32) -> f32 {
+    let t = linear(t);
+    if t < 0.5 {
+        cubic_in(2.0 * t) / 2.0
+    } else {
+        0.5 + cubic_out(2.0 * t - 1.0) / 2.0
+    }
+}
+
+/// Falls into the end like a ball, bouncing off it three times with decreasing height.
+pub fn bounce_out(t: f32) -> f32 {
+    // Four parabolas with the ball hitting the end at their borders. The first one is the fall,
+    // the other ones the bounces, each half as long as the previous one.
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    let t = linear(t);
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
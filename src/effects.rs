@@ -0,0 +1,361 @@
+//! Ready-made effects for the storey LEDs.
+//!
+//! Every effect is configured with its own parameters and turned into an [`Animation`] for `N`
+//! LEDs with `animation`. The animations cover one period of the effect and are meant to be
+//! repeated. Effects switching LEDs on and off are played with [`crate::led::Storeys::play`].
+//! Effects fading LEDs, like the [`Scanner`] and [`Breathing`], produce brightness levels and are
+//! played on dimmable LEDs with [`crate::led::Storeys::play_levels`]:
+//!
+//! ```rust,ignore
+//! let scanner = Scanner::new(Duration::from_millis(80)).with_tail(3);
+//! storeys.play_levels(scanner.animation().repeat_forever()).await;
+//! ```
+//!
+//! The animations don't depend on the hardware. They can be previewed on the host with
+//! `hakkaa::mock::preview` from the feature `mock`.
+
+use embassy_time::Duration;
+
+use crate::animation::{Animation, Step};
+use crate::frame::{Direction, Frame};
+use crate::gamma::Level;
+use crate::random::{xorshift, DEFAULT_SEED};
+
+/// Returns the position of a dot moving back and forth over `leds` LEDs at `step`. The dot starts
+/// at position 0 and turns around at both ends without lingering.
+fn back_and_forth(step: usize, leds: usize) -> usize {
+    let last = leds.saturating_sub(1);
+    let step = step % sweep_steps(leds);
+    if step <= last {
+        step
+    } else {
+        2 * last - step
+    }
+}
+
+/// Returns the number of steps for moving a dot over `leds` LEDs there and back again.
+fn sweep_steps(leds: usize) -> usize {
+    (2 * leds.saturating_sub(1)).max(1)
+}
+
+/// A dot sweeping back and forth with a fading tail, known from Knight Rider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Scanner {
+    step: Duration,
+    tail: usize,
+    direction: Direction,
+}
+
+impl Scanner {
+    /// The default number of LEDs in the fading tail.
+    pub const DEFAULT_TAIL: usize = 2;
+
+    /// Creates a scanner moving the dot by one LED every `step`.
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            tail: Self::DEFAULT_TAIL,
+            direction: Direction::Up,
+        }
+    }
+
+    /// Sets the number of LEDs behind the dot fading out. Without a tail, only the dot is lit.
+    pub fn with_tail(mut self, tail: usize) -> Self {
+        self.tail = tail;
+        self
+    }
+
+    /// Sets the direction of the first sweep.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Returns one sweep there and back again.
+    pub fn animation<const N: usize>(
+        self,
+    ) -> impl Animation<[Level; N]> + Clone + DoubleEndedIterator + ExactSizeIterator {
+        let steps = if N == 0 { 0 } else { sweep_steps(N) };
+
+        (0..steps).map(move |step| {
+            let mut levels = [Level::OFF; N];
+
+            // The tail follows the path the dot took before. Where the dot turns around, the tail
+            // and the dot share LEDs and the brighter one wins.
+            for age in 0..=self.tail {
+                let position = back_and_forth(step + steps * (age / steps + 1) - age, N);
+                let level = &mut levels[self.direction.index(position, N)];
+                let fade = (usize::from(u8::MAX) * (self.tail + 1 - age) / (self.tail + 1)) as u8;
+                *level = (*level).max(Level(fade));
+            }
+
+            Step::new(levels, self.step)
+        })
+    }
+}
+
+/// A dot falling down, bouncing off the bottom, and rising up again like a ball.
+///
+/// The dot speeds up while falling and slows down while rising. It lingers at the top and at
+/// the bottom LED accordingly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounce {
+    fall: Duration,
+    direction: Direction,
+}
+
+impl Bounce {
+    /// Creates a bouncing dot taking `fall` for falling from the top to the bottom LED. Rising up
+    /// again takes the same time.
+    pub fn new(fall: Duration) -> Self {
+        Self {
+            fall,
+            direction: Direction::Down,
+        }
+    }
+
+    /// Sets the direction of the fall. This is [`Direction::Down`] towards the LED at index 0 by
+    /// default.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Returns one bounce starting with the dot at the top.
+    pub fn animation<const N: usize>(
+        self,
+    ) -> impl Animation<Frame<N>> + Clone + DoubleEndedIterator + ExactSizeIterator {
+        let steps = if N <= 1 { N } else { sweep_steps(N) };
+        let top = N.saturating_sub(1);
+        let fall = u128::from(self.fall.as_micros());
+
+        // The time from the top until the dot has fallen to `height`, in half LEDs. The dot
+        // follows a parabola and takes the whole fall for falling from the top to the bottom.
+        let time = move |height: usize| {
+            let height = height.min(2 * top) as u128;
+            let time = (fall * fall * (2 * top as u128 - height) / (2 * top as u128)).isqrt();
+            Duration::from_micros(time as u64)
+        };
+
+        (0..steps).map(move |step| {
+            // The height of the dot above the bottom LED.
+            let height = top - back_and_forth(step, N);
+            let duration = if N == 1 {
+                self.fall * 2
+            } else if height == top {
+                // Lingering at the top on the way up and down.
+                time(2 * top - 1) * 2
+            } else if height == 0 {
+                // Bouncing off the bottom.
+                (self.fall - time(1)) * 2
+            } else {
+                time(2 * height - 1) - time(2 * height + 1)
+            };
+
+            let index = match self.direction {
+                Direction::Down => height,
+                Direction::Up => top - height,
+            };
+            Step::new(Frame::single(index), duration)
+        })
+    }
+}
+
+/// A bar filling up all LEDs one at a time and draining again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Fill {
+    step: Duration,
+    direction: Direction,
+}
+
+impl Fill {
+    /// Creates a bar growing or shrinking by one LED every `step`.
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            direction: Direction::Up,
+        }
+    }
+
+    /// Sets the direction the bar grows in.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Returns filling up the LEDs, starting with one LED lit, and draining them until none is
+    /// lit. Take the first `N` steps for just filling up.
+    pub fn animation<const N: usize>(
+        self,
+    ) -> impl Animation<Frame<N>> + Clone + DoubleEndedIterator + ExactSizeIterator {
+        (0..2 * N).map(move |step| {
+            let lit = if step < N { step + 1 } else { 2 * N - step - 1 };
+            let frame = match self.direction {
+                Direction::Up => Frame::FULL.shift_right(N - lit),
+                Direction::Down => Frame::FULL.shift_left(N - lit),
+            };
+            Step::new(frame, self.step)
+        })
+    }
+}
+
+/// Every few LEDs lit and moving along, known from theater marquees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TheaterChase {
+    step: Duration,
+    spacing: usize,
+    direction: Direction,
+}
+
+impl TheaterChase {
+    /// The default distance between the lit LEDs.
+    pub const DEFAULT_SPACING: usize = 3;
+
+    /// Creates a chase moving the lit LEDs by one every `step`.
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            spacing: Self::DEFAULT_SPACING,
+            direction: Direction::Up,
+        }
+    }
+
+    /// Sets the distance between the lit LEDs. A spacing of 3 lights every third LED.
+    pub fn with_spacing(mut self, spacing: usize) -> Self {
+        self.spacing = spacing.max(1);
+        self
+    }
+
+    /// Sets the direction the lit LEDs move in.
+    pub fn with_direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Returns moving the lit LEDs until the pattern repeats.
+    pub fn animation<const N: usize>(
+        self,
+    ) -> impl Animation<Frame<N>> + Clone + DoubleEndedIterator + ExactSizeIterator {
+        (0..self.spacing).map(move |step| {
+            let offset = match self.direction {
+                Direction::Up => step,
+                Direction::Down => (self.spacing - step) % self.spacing,
+            };
+            let frame = (0..N)
+                .filter(|index| index % self.spacing == offset)
+                .fold(Frame::EMPTY, |frame, index| frame.with(index, true));
+            Step::new(frame, self.step)
+        })
+    }
+}
+
+/// LEDs flashing up randomly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sparkle {
+    step: Duration,
+    threshold: u32,
+    seed: u32,
+}
+
+impl Sparkle {
+    /// The default fraction of LEDs lit at a time.
+    pub const DEFAULT_DENSITY: f32 = 0.2;
+
+    /// Creates a sparkle showing a new random set of LEDs every `step`.
+    pub fn new(step: Duration) -> Self {
+        Self {
+            step,
+            threshold: Self::threshold(Self::DEFAULT_DENSITY),
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    /// Sets the chance for each LED to be lit in a step, from 0.0 to 1.0.
+    pub fn with_density(mut self, density: f32) -> Self {
+        self.threshold = Self::threshold(density);
+        self
+    }
+
+    /// Sets the seed for the random numbers. The same seed results in the same sparkles. The
+    /// default is [`DEFAULT_SEED`].
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    fn threshold(density: f32) -> u32 {
+        // Float to integer casts saturate and map NaN to zero.
+        (density * u32::MAX as f32) as u32
+    }
+
+    /// Returns endless sparkles. Cloning the animation replays the same sparkles.
+    pub fn animation<const N: usize>(self) -> impl Animation<Frame<N>> + Clone {
+        // Xorshift gets stuck at zero.
+        let mut state = self.seed.max(1);
+
+        core::iter::repeat_with(move || {
+            let frame = (0..N).fold(Frame::EMPTY, |frame, index| {
+                state = xorshift(state);
+                frame.with(index, state < self.threshold)
+            });
+            Step::new(frame, self.step)
+        })
+    }
+}
+
+/// All LEDs slowly fading in and out like breathing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Breathing {
+    period: Duration,
+    step: Duration,
+    peak: Level,
+}
+
+impl Breathing {
+    /// The default time between brightness updates. This looks smooth to the eye.
+    pub const DEFAULT_STEP: Duration = Duration::from_millis(20);
+
+    /// Creates a breathing effect taking `period` for one breath in and out.
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            step: Self::DEFAULT_STEP,
+            peak: Level::FULL,
+        }
+    }
+
+    /// Sets the time between brightness updates.
+    pub fn with_step(mut self, step: Duration) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Sets the brightest level reached.
+    pub fn with_peak(mut self, peak: impl Into<Level>) -> Self {
+        self.peak = peak.into();
+        self
+    }
+
+    /// Returns one breath, starting and ending with the LEDs off.
+    pub fn animation<const N: usize>(
+        self,
+    ) -> impl Animation<[Level; N]> + Clone + DoubleEndedIterator + ExactSizeIterator {
+        const ONE: u64 = 1 << 16;
+        let period = self.period.as_ticks();
+        let steps = (period / self.step.as_ticks().max(1)).max(2);
+
+        (0..steps as usize).map(move |step| {
+            let step = step as u64;
+
+            // Go up and down linearly and smooth out the turning points.
+            let phase = step * 2 * ONE / steps;
+            let x = if phase < ONE { phase } else { 2 * ONE - phase };
+            let smooth = x * x / ONE * (3 * ONE - 2 * x) / ONE;
+            let level = Level((u64::from(self.peak.0) * smooth / ONE) as u8);
+
+            // Spread rounding errors so that the steps add up to the period exactly.
+            let duration = (step + 1) * period / steps - step * period / steps;
+            Step::new([level; N], Duration::from_ticks(duration))
+        })
+    }
+}
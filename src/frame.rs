@@ -0,0 +1,473 @@
+//! Frames holding the on/off state of a row of LEDs.
+//!
+//! A [`Frame`] is a bitmap with one bit per LED. It can be manipulated like an integer pattern, but
+//! it knows how many LEDs it is made for. So shifting, rotating and mirroring stays within the
+//! LEDs.
+//!
+//! Frames are best written as ASCII art. The character at index _n_ is the LED at index _n_, `#`
+//! for an LED switched on and `.` for an LED switched off:
+//!
+//! ```rust
+//! use hakkaa::frame::Frame;
+//!
+//! const ARROW: Frame = Frame::from_art("..####..");
+//!
+//! assert_eq!(ARROW.rotate_left(2).to_string(), "....####");
+//! assert_eq!(!ARROW, Frame::from_art("##....##"));
+//! assert!(ARROW[2]);
+//! ```
+//!
+//! Whole patterns of frames are written with the [`pattern!`](crate::pattern) macro.
+
+use core::fmt;
+use core::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not};
+use core::str::FromStr;
+
+use crate::gamma::Level;
+use crate::led::STOREY_LEDS;
+
+/// The character for an LED switched on in ASCII art.
+pub const ON: char = '#';
+
+/// The character for an LED switched off in ASCII art.
+pub const OFF: char = '.';
+
+/// The order of the LEDs in ASCII art.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BitOrder {
+    /// The first character is the LED at index 0. This is the order of [`Frame::from_art`] and of
+    /// [`Frame`]s as strings.
+    #[default]
+    LsbFirst,
+    /// The last character is the LED at index 0, like the last digit of a binary literal such as
+    /// `0b1100_0000`.
+    MsbFirst,
+}
+
+/// The direction of a movement along the LEDs, like of an effect or a transition.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// From the LED at index 0 towards the LED at index `N - 1`. These are D1 and D8 on the
+    /// Hakkaa board.
+    #[default]
+    Up,
+    /// From the LED at index `N - 1` towards the LED at index 0.
+    Down,
+}
+
+impl Direction {
+    /// Returns the index of the LED at `position` when counting in this direction.
+    pub(crate) fn index(self, position: usize, leds: usize) -> usize {
+        match self {
+            Self::Up => position,
+            Self::Down => leds - 1 - position,
+        }
+    }
+}
+
+/// The on/off state of `N` LEDs. The default are the [`STOREY_LEDS`] of the Hakkaa board and up to
+/// 64 LEDs are supported.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Frame<const N: usize = STOREY_LEDS> {
+    bits: u64,
+}
+
+impl<const N: usize> Frame<N> {
+    /// The bits used for `N` LEDs.
+    const MASK: u64 = {
+        assert!(N <= 64, "a frame supports up to 64 LEDs");
+        if N == 64 {
+            u64::MAX
+        } else {
+            (1 << N) - 1
+        }
+    };
+
+    /// All LEDs switched off.
+    pub const EMPTY: Self = Self { bits: 0 };
+
+    /// All LEDs switched on.
+    pub const FULL: Self = Self { bits: Self::MASK };
+
+    /// Creates a frame from a bit pattern. The bit at index _n_ is the state of the LED at index
+    /// _n_. Bits beyond the `N` LEDs are ignored.
+    pub const fn from_bits(bits: u64) -> Self {
+        Self {
+            bits: bits & Self::MASK,
+        }
+    }
+
+    /// Returns the bit pattern of this frame. This is the counterpart to [`Frame::from_bits`].
+    pub const fn bits(self) -> u64 {
+        self.bits
+    }
+
+    /// Creates a frame with only the LED at `index` switched on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub const fn single(index: usize) -> Self {
+        Self::EMPTY.with(index, true)
+    }
+
+    /// Creates a frame from ASCII art like `"#..#..##"`. This is meant for defining frames as
+    /// constants. Use [`str::parse`] for handling invalid art at runtime.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the art does not consist of exactly `N` characters `#` and `.`. For constants,
+    /// this turns into a compile error.
+    pub const fn from_art(art: &str) -> Self {
+        match Self::parse(art) {
+            Ok(frame) => frame,
+            Err(ParseFrameError::Length { .. }) => panic!("frame art has the wrong length"),
+            Err(ParseFrameError::Character { .. }) => {
+                panic!("frame art contains invalid characters")
+            }
+        }
+    }
+
+    /// Creates a frame from ASCII art like [`Frame::from_art`], with the LEDs in the given `order`.
+    ///
+    /// # Panics
+    ///
+    /// Panics like [`Frame::from_art`].
+    pub const fn from_art_with(art: &str, order: BitOrder) -> Self {
+        let frame = Self::from_art(art);
+        match order {
+            BitOrder::LsbFirst => frame,
+            BitOrder::MsbFirst => frame.mirror(),
+        }
+    }
+
+    const fn parse(art: &str) -> Result<Self, ParseFrameError> {
+        let bytes = art.as_bytes();
+        let mut bits = 0;
+
+        let mut index = 0;
+        while index < bytes.len() {
+            match bytes[index] {
+                // Longer art is rejected below, just don't overflow the bits.
+                b'#' if index < N => bits |= 1 << index,
+                b'#' => {}
+                b'.' => {}
+                _ => return Err(ParseFrameError::Character { index }),
+            }
+            index += 1;
+        }
+
+        if bytes.len() != N {
+            return Err(ParseFrameError::Length {
+                expected: N,
+                found: bytes.len(),
+            });
+        }
+
+        Ok(Self { bits })
+    }
+
+    /// Returns whether the LED at `index` is switched on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub const fn get(self, index: usize) -> bool {
+        assert!(index < N, "LED index out of range");
+        (self.bits >> index) & 1 == 1
+    }
+
+    /// Switches the LED at `index` on or off.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn set(&mut self, index: usize, on: bool) {
+        *self = self.with(index, on);
+    }
+
+    /// Returns this frame with the LED at `index` switched on or off.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub const fn with(self, index: usize, on: bool) -> Self {
+        assert!(index < N, "LED index out of range");
+        Self {
+            bits: (self.bits & !(1 << index)) | ((on as u64) << index),
+        }
+    }
+
+    /// Returns the number of LEDs switched on.
+    pub const fn count_on(self) -> u32 {
+        self.bits.count_ones()
+    }
+
+    /// Returns whether all LEDs are switched off.
+    pub const fn is_off(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns whether all LEDs switched on in `other` are switched on in this frame as well.
+    pub const fn contains(self, other: Self) -> bool {
+        self.bits & other.bits == other.bits
+    }
+
+    /// Moves the pattern `n` LEDs towards higher indices. LEDs moving out of the frame are lost,
+    /// LEDs moving in are switched off.
+    pub const fn shift_left(self, n: usize) -> Self {
+        if n >= N {
+            Self::EMPTY
+        } else {
+            Self::from_bits(self.bits << n)
+        }
+    }
+
+    /// Moves the pattern `n` LEDs towards lower indices. LEDs moving out of the frame are lost,
+    /// LEDs moving in are switched off.
+    pub const fn shift_right(self, n: usize) -> Self {
+        if n >= N {
+            Self::EMPTY
+        } else {
+            Self::from_bits(self.bits >> n)
+        }
+    }
+
+    /// Moves the pattern `n` LEDs towards higher indices. LEDs moving out at the end come back in
+    /// at the start.
+    pub const fn rotate_left(self, n: usize) -> Self {
+        let n = if N == 0 { 0 } else { n % N };
+        if n == 0 {
+            return self;
+        }
+        Self::from_bits((self.bits << n) | (self.bits >> (N - n)))
+    }
+
+    /// Moves the pattern `n` LEDs towards lower indices. LEDs moving out at the start come back in
+    /// at the end.
+    pub const fn rotate_right(self, n: usize) -> Self {
+        if N == 0 {
+            return self;
+        }
+        self.rotate_left(N - n % N)
+    }
+
+    /// Reverses the order of the LEDs. The LED at index 0 swaps places with the LED at index
+    /// `N - 1` and so on.
+    pub const fn mirror(self) -> Self {
+        if N == 0 {
+            return self;
+        }
+        Self::from_bits(self.bits.reverse_bits() >> (64 - N))
+    }
+
+    /// Switches all LEDs which are on off and vice versa. This is the same as `!frame`.
+    pub const fn invert(self) -> Self {
+        Self::from_bits(!self.bits)
+    }
+
+    /// Returns an iterator over the states of the LEDs, starting at index 0.
+    pub fn iter(self) -> impl Iterator<Item = bool> {
+        (0..N).map(move |index| self.get(index))
+    }
+}
+
+impl Frame<STOREY_LEDS> {
+    /// Only the storey LED D1 switched on.
+    pub const D1: Self = Self::single(0);
+    /// Only the storey LED D2 switched on.
+    pub const D2: Self = Self::single(1);
+    /// Only the storey LED D3 switched on.
+    pub const D3: Self = Self::single(2);
+    /// Only the storey LED D4 switched on.
+    pub const D4: Self = Self::single(3);
+    /// Only the storey LED D5 switched on.
+    pub const D5: Self = Self::single(4);
+    /// Only the storey LED D6 switched on.
+    pub const D6: Self = Self::single(5);
+    /// Only the storey LED D7 switched on.
+    pub const D7: Self = Self::single(6);
+    /// Only the storey LED D8 switched on.
+    pub const D8: Self = Self::single(7);
+}
+
+impl<const N: usize> Index<usize> for Frame<N> {
+    type Output = bool;
+
+    fn index(&self, index: usize) -> &bool {
+        if self.get(index) {
+            &true
+        } else {
+            &false
+        }
+    }
+}
+
+impl<const N: usize> From<[bool; N]> for Frame<N> {
+    fn from(leds: [bool; N]) -> Self {
+        leds.iter()
+            .enumerate()
+            .fold(Self::EMPTY, |frame, (index, on)| frame.with(index, *on))
+    }
+}
+
+impl<const N: usize> From<Frame<N>> for [bool; N] {
+    fn from(frame: Frame<N>) -> Self {
+        core::array::from_fn(|index| frame.get(index))
+    }
+}
+
+impl<const N: usize> From<Frame<N>> for [Level; N] {
+    /// Converts the frame to brightness levels with the LEDs switched on at full brightness.
+    fn from(frame: Frame<N>) -> Self {
+        core::array::from_fn(|index| {
+            if frame.get(index) {
+                Level::FULL
+            } else {
+                Level::OFF
+            }
+        })
+    }
+}
+
+impl<const N: usize> Not for Frame<N> {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        self.invert()
+    }
+}
+
+macro_rules! impl_bit_op {
+    ($op:ident, $method:ident, $op_assign:ident, $method_assign:ident) => {
+        impl<const N: usize> $op for Frame<N> {
+            type Output = Self;
+
+            fn $method(self, other: Self) -> Self {
+                Self {
+                    bits: self.bits.$method(other.bits),
+                }
+            }
+        }
+
+        impl<const N: usize> $op_assign for Frame<N> {
+            fn $method_assign(&mut self, other: Self) {
+                *self = self.$method(other);
+            }
+        }
+    };
+}
+
+impl_bit_op!(BitAnd, bitand, BitAndAssign, bitand_assign);
+impl_bit_op!(BitOr, bitor, BitOrAssign, bitor_assign);
+impl_bit_op!(BitXor, bitxor, BitXorAssign, bitxor_assign);
+
+impl<const N: usize> fmt::Display for Frame<N> {
+    /// Formats the frame as ASCII art like `"#..#..##"`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.iter()
+            .try_for_each(|on| fmt::Write::write_char(f, if on { ON } else { OFF }))
+    }
+}
+
+impl<const N: usize> fmt::Debug for Frame<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Frame(\"{self}\")")
+    }
+}
+
+impl<const N: usize> FromStr for Frame<N> {
+    type Err = ParseFrameError;
+
+    /// Parses ASCII art like `"#..#..##"`.
+    fn from_str(art: &str) -> Result<Self, Self::Err> {
+        Self::parse(art)
+    }
+}
+
+/// An error from parsing a [`Frame`] from ASCII art.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseFrameError {
+    /// The art does not have one character per LED.
+    Length {
+        /// The number of LEDs in the frame.
+        expected: usize,
+        /// The number of characters in the art.
+        found: usize,
+    },
+    /// The art contains a character other than `#` and `.`.
+    Character {
+        /// The byte index of the character.
+        index: usize,
+    },
+}
+
+impl fmt::Display for ParseFrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Length { expected, found } => {
+                write!(f, "expected {expected} LEDs but found {found}")
+            }
+            Self::Character { index } => {
+                write!(
+                    f,
+                    "invalid character at index {index}, expected '{ON}' or '{OFF}'"
+                )
+            }
+        }
+    }
+}
+
+impl core::error::Error for ParseFrameError {}
+
+/// Creates an array of [`Frame`]s from rows of ASCII art at compile time.
+///
+/// Each row is a string like for [`Frame::from_art`]. The number of LEDs is taken from where the
+/// pattern goes to:
+///
+/// ```rust
+/// use hakkaa::frame::Frame;
+/// use hakkaa::pattern;
+///
+/// static ARROWS: [Frame; 2] = pattern![
+///     "..##....",
+///     "#......#",
+/// ];
+///
+/// assert_eq!(ARROWS[1], Frame::D1 | Frame::D8);
+/// ```
+///
+/// The rows are checked at compile time. A row with the wrong number of LEDs or with characters
+/// other than `#` and `.` does not compile, even in a function:
+///
+/// ```rust,compile_fail
+/// let arrows: [hakkaa::frame::Frame; 2] = hakkaa::pattern![
+///     "..##....",
+///     "#.....#",
+/// ];
+/// ```
+///
+/// A [`BitOrder`] before the rows changes the order of the LEDs. With `MsbFirst`, rows read like
+/// binary literals:
+///
+/// ```rust
+/// use hakkaa::frame::Frame;
+/// use hakkaa::pattern;
+///
+/// static ARROWS: [Frame; 1] = pattern![MsbFirst; "......##"];
+///
+/// assert_eq!(ARROWS[0], Frame::from_bits(0b0000_0011));
+/// ```
+#[macro_export]
+macro_rules! pattern {
+    ($order:ident; $($row:expr),+ $(,)?) => {
+        const {
+            [$(
+                $crate::frame::Frame::from_art_with($row, $crate::frame::BitOrder::$order)
+            ),+]
+        }
+    };
+    ($($row:expr),+ $(,)?) => {
+        $crate::pattern![LsbFirst; $($row),+]
+    };
+}
@@ -0,0 +1,176 @@
+//! Gamma correction for getting perceptually even brightness steps from LEDs.
+//!
+//! The brightness of an LED grows linearly with its duty cycle, but our eyes don't see it this
+//! way: The step from 1 % to 2 % looks huge while the step from 90 % to 100 % is barely visible.
+//! Perceived brightness roughly follows a power function of the duty cycle. A [`GammaTable`] maps
+//! perceptual brightness [`Level`]s to duty cycles with the inverse of this function.
+//!
+//! Gamma tables are computed at compile time. [`GAMMA`] is used by default and further tables for
+//! a different gamma exponent can be created as constants:
+//!
+//! ```rust
+//! use hakkaa::gamma::GammaTable;
+//!
+//! const MY_GAMMA: GammaTable = GammaTable::new(2.2);
+//! ```
+
+/// The default gamma exponent. Values between 2.2 and 3.0 look good for LEDs.
+pub const DEFAULT_GAMMA: f32 = 2.8;
+
+/// The gamma table for [`DEFAULT_GAMMA`] used by default.
+pub static GAMMA: GammaTable = GammaTable::new(DEFAULT_GAMMA);
+
+/// A perceptual brightness level from 0 (off) to 255 (full brightness).
+///
+/// Levels can be created from `u8` values and from `f32` values between 0.0 and 1.0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Level(pub u8);
+
+impl Level {
+    /// The level of a switched off LED.
+    pub const OFF: Level = Level(0);
+    /// The level of an LED at full brightness.
+    pub const FULL: Level = Level(u8::MAX);
+}
+
+impl From<u8> for Level {
+    fn from(level: u8) -> Self {
+        Self(level)
+    }
+}
+
+impl From<f32> for Level {
+    /// Creates a level from a fraction of the full brightness. Values outside of the range from
+    /// 0.0 to 1.0 are clamped.
+    fn from(level: f32) -> Self {
+        // Float to integer casts saturate and map NaN to zero.
+        Self((level * f32::from(u8::MAX) + 0.5) as u8)
+    }
+}
+
+/// A lookup table mapping perceptual brightness [`Level`]s to duty cycles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GammaTable {
+    duties: [u8; 256],
+}
+
+impl GammaTable {
+    /// Computes the table for the given gamma exponent. A gamma of 1.0 results in a linear
+    /// mapping.
+    ///
+    /// Every level above zero gets mapped to a duty cycle above zero. So a dimmed LED is still
+    /// switched on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `gamma` is not a positive, finite number. In a constant, this fails the build.
+    pub const fn new(gamma: f32) -> Self {
+        assert!(
+            gamma.is_finite() && gamma > 0.0,
+            "the gamma exponent needs to be positive and finite"
+        );
+        let gamma = gamma as f64;
+        let max = u8::MAX as f64;
+        let mut duties = [0; 256];
+
+        let mut level = 1;
+        while level < duties.len() {
+            let fraction = level as f64 / max;
+            let duty = (max * exp(gamma * ln(fraction)) + 0.5) as u8;
+            duties[level] = if duty > 0 { duty } else { 1 };
+            level += 1;
+        }
+
+        Self { duties }
+    }
+
+    /// Returns the duty cycle for the given perceptual brightness level.
+    pub const fn duty(&self, level: Level) -> u8 {
+        self.duties[level.0 as usize]
+    }
+
+    /// Returns the lowest perceptual brightness level mapped to at least the given duty cycle.
+    /// This is the inverse of [`GammaTable::duty`] as far as the table allows: Several dim levels
+    /// share the same duty cycle.
+    pub fn level(&self, duty: u8) -> Level {
+        Level(self.duties.partition_point(|d| *d < duty) as u8)
+    }
+}
+
+/// The natural logarithm, usable in constants. This is NaN for NaN and negative values.
+pub(crate) const fn ln(x: f64) -> f64 {
+    // The argument reduction below never ends for these.
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if x == f64::INFINITY {
+        return f64::INFINITY;
+    }
+
+    // Reduce the argument to the range from 1 to 2 and sum up the series of 2 * atanh(z) for
+    // (1 + z) / (1 - z) = x. It converges quickly in this range.
+    let mut mantissa = x;
+    let mut exponent = 0;
+    while mantissa >= 2.0 {
+        mantissa /= 2.0;
+        exponent += 1;
+    }
+    while mantissa < 1.0 {
+        mantissa *= 2.0;
+        exponent -= 1;
+    }
+
+    let z = (mantissa - 1.0) / (mantissa + 1.0);
+    let mut power = z;
+    let mut sum = 0.0;
+    let mut n = 1;
+    while n < 40 {
+        sum += power / n as f64;
+        power *= z * z;
+        n += 2;
+    }
+
+    2.0 * sum + exponent as f64 * core::f64::consts::LN_2
+}
+
+/// The exponential function, usable in constants.
+const fn exp(x: f64) -> f64 {
+    // The argument reduction below never ends for these.
+    if x.is_nan() {
+        return f64::NAN;
+    }
+    if x == f64::INFINITY {
+        return f64::INFINITY;
+    }
+    if x == f64::NEG_INFINITY {
+        return 0.0;
+    }
+
+    // Halve the argument until the Taylor series converges quickly and square the result the
+    // same number of times.
+    let mut reduced = x;
+    let mut halvings = 0;
+    while reduced > 0.5 || reduced < -0.5 {
+        reduced /= 2.0;
+        halvings += 1;
+    }
+
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut n = 1;
+    while n < 20 {
+        term *= reduced / n as f64;
+        sum += term;
+        n += 1;
+    }
+
+    while halvings > 0 {
+        sum *= sum;
+        halvings -= 1;
+    }
+
+    sum
+}
@@ -1,37 +1,287 @@
 //! Higher level abstractions for LEDs, like for cycling the storey LEDs.
 
-use crate::switch::LowActiveSwitch;
-use embassy_time::{Duration, Ticker};
+use crate::animation::{self, Animation, Keyframes};
+use crate::diag::{classify, LedStatus, ProbePin, Pull, Readings, SETTLING_TIME};
+use crate::frame::Frame;
+use crate::gamma::{GammaTable, Level, GAMMA};
+use crate::mapping::Mapping;
+use crate::meter::Meter;
+use crate::number::Number;
+use crate::power::{Budget, Strategy};
+use crate::switch::{Dimmable, LowActiveSwitch, Port, Snapshot, Switch};
+use crate::transition::Transition;
+use core::convert::Infallible;
+use embassy_time::{Duration, Instant, Timer};
+use embedded_hal::digital::OutputPin;
+
+mod layer;
+
+pub use layer::{Blend, Layer, LayerId, LayerStack};
 
 /// The number of storey LEDs on the board.
 pub const STOREY_LEDS: usize = 8;
 
+/// Bit patterns for switching LEDs on and off. The bit at index _n_ is the state of the LED at
+/// index _n_.
+pub trait Pattern: Copy + Default + Eq + core::fmt::Debug {
+    /// Returns whether the bit at `index` is set.
+    fn bit(self, index: usize) -> bool;
+
+    /// Returns this pattern with the bit at `index` set to `on`.
+    fn with_bit(self, index: usize, on: bool) -> Self;
+}
+
+macro_rules! impl_pattern {
+    ($($pattern:ty),*) => {
+        $(
+            impl Pattern for $pattern {
+                fn bit(self, index: usize) -> bool {
+                    (self >> index) & 1 == 1
+                }
+
+                fn with_bit(self, index: usize, on: bool) -> Self {
+                    (self & !(1 << index)) | (Self::from(on) << index)
+                }
+            }
+        )*
+    };
+}
+
+impl_pattern!(u8, u16, u32, u64);
+
+/// Marker for selecting the [`Pattern`] type for `N` LEDs through [`PatternFor`].
+#[derive(Debug)]
+pub struct Leds<const N: usize>;
+
+/// Selects the smallest unsigned integer holding a bit for each of the `N` LEDs from [`Leds<N>`]
+/// as pattern type. This is `u8` for the eight storey LEDs of the Hakkaa board and `u16` for
+/// boards with 12 or 16 LEDs. Patterns are available for up to 64 LEDs.
+pub trait PatternFor {
+    /// The pattern type for the LEDs.
+    type Pattern: Pattern;
+}
+
+macro_rules! impl_pattern_for {
+    ($pattern:ty: $($n:literal)*) => {
+        $(
+            impl PatternFor for Leds<$n> {
+                type Pattern = $pattern;
+            }
+        )*
+    };
+}
+
+impl_pattern_for!(u8: 0 1 2 3 4 5 6 7 8);
+impl_pattern_for!(u16: 9 10 11 12 13 14 15 16);
+impl_pattern_for!(u32: 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31 32);
+impl_pattern_for!(u64: 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48
+    49 50 51 52 53 54 55 56 57 58 59 60 61 62 63 64);
+
+/// The pattern type for `N` LEDs. See [`PatternFor`].
+pub type PatternOf<const N: usize> = <Leds<N> as PatternFor>::Pattern;
+
 /// Convenience wrapper proviving higher-level functionality for all the storey LEDs like for
 /// example cycling one switched on led.
+///
+/// The storey LEDs are `N` switches of type `S`. This defaults to the [`STOREY_LEDS`] of the Hakkaa
+/// board, but boards with a different number of LEDs can use `Storeys` as well:
+///
+/// ```rust,ignore
+/// let mut storeys: Storeys<_, 12> = Storeys::new(leds);
+/// storeys.set_pattern(0b1010_1010_1010u16);
+/// ```
+///
+/// Frames, patterns and brightness arrays address the LEDs by their logical index. A [`Mapping`]
+/// translates it to the index into the switches. By default, both are the same.
 #[derive(Debug)]
-pub struct Storeys<'a> {
-    leds: [LowActiveSwitch<'a>; 8],
+pub struct Storeys<S, const N: usize = STOREY_LEDS> {
+    leds: [S; N],
+    gamma: &'static GammaTable,
+    mapping: Mapping<N>,
+    budget: Option<Budget>,
+    port: Option<Port<N>>,
+    back_buffer: Frame<N>,
 }
 
-impl<'a> Storeys<'a> {
-    pub fn new(leds: [LowActiveSwitch<'a>; 8]) -> Self {
-        Self { leds }
+impl<S: Switch, const N: usize> Storeys<S, N> {
+    pub fn new(leds: [S; N]) -> Self {
+        Self {
+            leds,
+            gamma: &GAMMA,
+            mapping: Mapping::IDENTITY,
+            budget: None,
+            port: None,
+            back_buffer: Frame::EMPTY,
+        }
+    }
+
+    /// Sets the mapping from the logical LEDs of frames and patterns to the switches. The default
+    /// is [`Mapping::IDENTITY`].
+    pub fn with_mapping(mut self, mapping: Mapping<N>) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    /// Changes the mapping from the logical LEDs to the switches at runtime, like when the board
+    /// gets turned upside down. The LEDs keep showing the current frame.
+    pub fn set_mapping(&mut self, mapping: Mapping<N>) {
+        let frame = self.frame();
+        self.mapping = mapping;
+        self.set_frame(frame);
+    }
+
+    /// Returns the mapping from the logical LEDs to the switches.
+    pub fn mapping(&self) -> Mapping<N> {
+        self.mapping
+    }
+
+    /// Limits the current drawn by the storey LEDs to `budget`. Frames, patterns and brightness
+    /// get limited according to its [`Strategy`]. By default, there is no limit.
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Returns the budget limiting the current drawn by the storey LEDs, if any.
+    pub fn budget(&self) -> Option<Budget> {
+        self.budget
+    }
+
+    /// Switches the LEDs for a frame all at once through `port` instead of one after another. This
+    /// avoids smearing with persistence of vision. The outputs of the port are in the order of the
+    /// switches. They need to be the outputs of the switches, which take note of their new state
+    /// with [`Switch::follow`] without writing their outputs again.
+    pub fn with_port(mut self, port: Port<N>) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Returns the switch for the logical LED at `index`.
+    fn led(&self, index: usize) -> &S {
+        &self.leds[self.mapping.physical(index)]
+    }
+
+    /// Returns the switch for the logical LED at `index` for changing it.
+    fn led_mut(&mut self, index: usize) -> &mut S {
+        &mut self.leds[self.mapping.physical(index)]
     }
 
-    pub fn free(self) -> [LowActiveSwitch<'a>; 8] {
+    /// Sets the gamma table for mapping perceptual brightness levels to duty cycles in
+    /// [`Storeys::set_levels`]. The default is [`GAMMA`].
+    pub fn with_gamma(mut self, gamma: &'static GammaTable) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    pub fn free(self) -> [S; N] {
         self.leds
     }
 
     /// Switches all storey LEDs off.
     pub fn all_off(&mut self) {
         log::debug!("Dn off");
-        self.leds.iter_mut().for_each(|led| led.switch_off());
+        self.set_frame(Frame::EMPTY);
     }
 
-    /// Switches all storey LEDs on.
+    /// Switches all storey LEDs on, as far as the budget allows.
     pub fn all_on(&mut self) {
         log::debug!("Dn on");
-        self.leds.iter_mut().for_each(|led| led.switch_on());
+        self.set_frame(Frame::FULL);
+    }
+
+    /// Shows the supplied frame on the storey LEDs. The LED at index _n_ of the frame specifies the
+    /// output state of the LED at index _n_ from the array `leds` passed to [`Storeys::new`], or
+    /// where the mapping puts it. LEDs beyond the budget stay off.
+    pub fn set_frame(&mut self, frame: Frame<N>) {
+        let frame = match self.budget {
+            Some(budget) => budget.limit_frame(frame),
+            None => frame,
+        };
+        let frame = self.mapping.map(frame);
+        match &self.port {
+            Some(port) => {
+                port.write(frame);
+                // The outputs are already switched. The switches just need to follow along.
+                for (led, on) in self.leds.iter_mut().zip(frame.iter()) {
+                    led.follow(on);
+                }
+            }
+            None => {
+                for (led, on) in self.leds.iter_mut().zip(frame.iter()) {
+                    led.switch(on);
+                }
+            }
+        }
+    }
+
+    /// Returns the frame currently shown by the storey LEDs. This is the counterpart to
+    /// [`Storeys::set_frame`].
+    pub fn frame(&self) -> Frame<N> {
+        let physical = core::array::from_fn(|i| self.leds[i].is_on()).into();
+        self.mapping.unmap(physical)
+    }
+
+    /// Returns the frame prepared in the back buffer. This is not shown until it gets committed
+    /// with [`Storeys::commit`] or [`Storeys::commit_at`].
+    pub fn back_buffer(&self) -> Frame<N> {
+        self.back_buffer
+    }
+
+    /// Returns the back buffer for preparing the next frame in place.
+    pub fn back_buffer_mut(&mut self) -> &mut Frame<N> {
+        &mut self.back_buffer
+    }
+
+    /// Prepares `frame` in the back buffer.
+    pub fn set_back_buffer(&mut self, frame: Frame<N>) {
+        self.back_buffer = frame;
+    }
+
+    /// Shows the frame from the back buffer right away. The back buffer keeps the frame for
+    /// preparing the next one from it.
+    pub fn commit(&mut self) {
+        self.set_frame(self.back_buffer);
+    }
+
+    /// Shows the frame from the back buffer at `at`. The frame shows up at the given instant no
+    /// matter how long it took to prepare it, as long as it was ready in time. Otherwise, it shows
+    /// up right away.
+    pub async fn commit_at(&mut self, at: Instant) {
+        Timer::at(at).await;
+        self.commit();
+    }
+
+    /// Shows `value` between `min` and `max` as a bar graph. Use [`Storeys::show_level_with`] for
+    /// other styles and scales.
+    pub fn show_level(&mut self, value: f32, min: f32, max: f32) {
+        self.show_level_with(value, min, max, Meter::default());
+    }
+
+    /// Shows `value` between `min` and `max` with the given meter.
+    pub fn show_level_with(&mut self, value: f32, min: f32, max: f32, meter: Meter) {
+        self.set_frame(meter.frame(value, min, max));
+    }
+
+    /// Shows `number` page by page, each page for `page`. The `marker` LED is lit while the first
+    /// page is shown for telling where the number starts. The blue ESP LED makes a good marker.
+    ///
+    /// All LEDs are switched off for a short gap after each page. This keeps subsequent pages
+    /// showing the same pattern apart.
+    pub async fn show_number(&mut self, number: Number, marker: &mut impl Switch, page: Duration) {
+        let mut deadline = Instant::now();
+        let gap = page / 4;
+
+        for (index, frame) in number.pages().enumerate() {
+            marker.switch(index == 0);
+            self.set_frame(frame);
+            deadline += page;
+            Timer::at(deadline).await;
+
+            marker.switch_off();
+            self.all_off();
+            deadline += gap;
+            Timer::at(deadline).await;
+        }
     }
 
     /// Blinks all storey LEDs simultaneously.
@@ -40,14 +290,8 @@ impl<'a> Storeys<'a> {
     /// will block forever. Use [`embassy_futures::select::select`] and friends to blink the LEDs
     /// while waiting for some other event to happen.
     pub async fn blink(&mut self, step: Duration) {
-        let mut ticker = Ticker::every(step);
-
-        loop {
-            self.all_on();
-            ticker.next().await;
-            self.all_off();
-            ticker.next().await;
-        }
+        log::debug!("Dn blink");
+        self.play(animation::blink(step).repeat_forever()).await;
     }
 
     /// Cycles through all storey LEDs, switching on one at a time.
@@ -56,29 +300,190 @@ impl<'a> Storeys<'a> {
     /// will block forever. Use [`embassy_futures::select::select`] and friends to cycle the LEDs
     /// while waiting for some other event to happen.
     pub async fn cycle(&mut self, step: Duration) {
-        let mut ticker = Ticker::every(step);
+        log::debug!("Dn cycle");
+        self.play(animation::cycle(step).repeat_forever()).await;
+    }
 
-        self.all_off();
+    /// Plays `animation` on the storey LEDs. The returned future completes when the animation
+    /// ends, which is never for endless animations.
+    ///
+    /// The frames are timed from the start of the animation. So slow frame computations or a busy
+    /// executor delay single frames but do not make the animation drift.
+    ///
+    /// With a budget for [`Strategy::Multiplex`], frames with more LEDs than the budget allows are
+    /// shown by cycling through groups of LEDs fitting into it.
+    pub async fn play(&mut self, animation: impl Animation<Frame<N>>) {
+        let mut deadline = Instant::now();
 
-        loop {
-            for n in 0..self.leds.len() {
-                log::debug!("cycle D{}", n + 1);
+        for step in animation {
+            deadline += step.duration;
+            self.show_until(step.frame, deadline).await;
+        }
+    }
+
+    /// Plays `keyframes` according to their loop mode. The returned future completes after the
+    /// last frame, which is never for endless loop modes. Like [`Storeys::play`], this times the
+    /// frames from the start, so even loops running for hours keep their pace.
+    pub async fn play_keyframes(&mut self, keyframes: Keyframes<Frame<N>>) {
+        self.play(keyframes.steps()).await;
+    }
 
-                let previous = (n + self.leds.len() - 1) % self.leds.len();
-                self.leds[previous].switch_off();
-                self.leds[n].switch_on();
-                ticker.next().await;
+    /// Shows `frame` until `deadline`, multiplexing it if the budget asks for it.
+    async fn show_until(&mut self, frame: Frame<N>, deadline: Instant) {
+        if let Some(budget) = self.budget {
+            if let Strategy::Multiplex(slot) = budget.strategy() {
+                if frame.count_on() as usize > budget.max_leds() {
+                    let mut next = Instant::now();
+                    for group in budget.groups(frame).cycle() {
+                        if next >= deadline {
+                            break;
+                        }
+                        self.set_frame(group);
+                        next += slot;
+                        Timer::at(next.min(deadline)).await;
+                    }
+                    return;
+                }
             }
         }
+
+        self.set_frame(frame);
+        Timer::at(deadline).await;
+    }
+
+    /// Removes the expired layers from `layers` and shows what remains of them on the storey
+    /// LEDs. Call this again when changing the layers and at [`LayerStack::next_expiry`].
+    pub fn show_layers<const L: usize>(&mut self, layers: &mut LayerStack<N, L>) {
+        layers.expire(Instant::now());
+        self.set_frame(layers.render());
     }
+}
 
+impl<S: Switch, const N: usize> Storeys<S, N>
+where
+    Leds<N>: PatternFor,
+{
     /// Sets the storey LEDs to the supplied pattern. The bit at index _n_ specifies the output
-    /// state of the LED at index _n_ from the array `leds` passed to [`Storeys::new`].
-    pub fn set_pattern(&mut self, pattern: u8) {
-        for i in 0..self.leds.len() {
-            let bit = (pattern >> i) & 1;
-            let on = bit == 1;
-            self.leds[i].switch(on);
+    /// state of the LED at index _n_ from the array `leds` passed to [`Storeys::new`], or where
+    /// the mapping puts it.
+    pub fn set_pattern(&mut self, pattern: PatternOf<N>) {
+        let frame = (0..N).fold(Frame::EMPTY, |frame, i| frame.with(i, pattern.bit(i)));
+        self.set_frame(frame);
+    }
+
+    /// Returns the pattern currently shown by the storey LEDs. The bit at index _n_ is set if the
+    /// LED at index _n_ is switched on. This is the counterpart to [`Storeys::set_pattern`].
+    pub fn pattern(&self) -> PatternOf<N> {
+        (0..N).fold(Default::default(), |pattern: PatternOf<N>, i| {
+            pattern.with_bit(i, self.led(i).is_on())
+        })
+    }
+}
+
+impl<S: Dimmable, const N: usize> Storeys<S, N> {
+    /// Sets the brightness of the storey LEDs. The element at index _n_ specifies the brightness
+    /// of the LED at index _n_ from the array `leds` passed to [`Storeys::new`], or where the
+    /// mapping puts it. The brightness gets limited to the budget.
+    pub fn set_brightness(&mut self, brightness: [u8; N]) {
+        let brightness = match self.budget {
+            Some(budget) => budget.limit_duties(brightness),
+            None => brightness,
+        };
+        for (i, brightness) in brightness.into_iter().enumerate() {
+            self.led_mut(i).set_brightness(brightness);
+        }
+    }
+
+    /// Returns the brightness currently set for the storey LEDs. This is the counterpart to
+    /// [`Storeys::set_brightness`].
+    pub fn brightness(&self) -> [u8; N] {
+        core::array::from_fn(|i| self.led(i).brightness())
+    }
+
+    /// Returns the perceptual brightness levels of the storey LEDs. They are derived from the
+    /// current brightness with the gamma table. This is the counterpart to
+    /// [`Storeys::set_levels`].
+    pub fn levels(&self) -> [Level; N] {
+        core::array::from_fn(|i| self.gamma.level(self.led(i).brightness()))
+    }
+
+    /// Sets the perceptual brightness levels of the storey LEDs, either from 0 to 255 or from 0.0
+    /// to 1.0. In contrast to [`Storeys::set_brightness`], the levels are gamma corrected for
+    /// getting evenly looking brightness steps.
+    pub fn set_levels<L: Into<Level>>(&mut self, levels: [L; N]) {
+        let gamma = self.gamma;
+        self.set_brightness(levels.map(|level| gamma.duty(level.into())));
+    }
+
+    /// Shows `value` between `min` and `max` with the given meter. In contrast to
+    /// [`Storeys::show_level_with`], the LED at the end of the bar is partially lit for showing
+    /// values in between two LEDs.
+    pub fn show_level_dimmed(&mut self, value: f32, min: f32, max: f32, meter: Meter) {
+        self.set_levels(meter.levels(value, min, max));
+    }
+
+    /// Changes the storey LEDs from their current levels to the supplied frame or levels with
+    /// `transition`.
+    pub async fn transition_to(&mut self, to: impl Into<[Level; N]>, transition: Transition) {
+        let from = self.levels();
+        self.play_levels(transition.animation(from, to)).await;
+    }
+
+    /// Plays an animation made of perceptual brightness levels on the storey LEDs. This is the
+    /// dimmed counterpart to [`Storeys::play`] with the levels shown by [`Storeys::set_levels`].
+    pub async fn play_levels<L: Into<Level>>(&mut self, animation: impl Animation<[L; N]>) {
+        let mut deadline = Instant::now();
+
+        for step in animation {
+            self.set_levels(step.frame);
+            deadline += step.duration;
+            Timer::at(deadline).await;
+        }
+    }
+}
+
+impl<P: OutputPin<Error = Infallible> + ProbePin, const N: usize> Storeys<LowActiveSwitch<P>, N> {
+    /// Checks whether the storey LEDs are present or whether they are missing, badly soldered, or
+    /// bridged to each other. See [`crate::diag`] for details.
+    ///
+    /// The LEDs are released for taking the readings and are set back to their previous pattern
+    /// afterwards. The results are in the order of the array `leds` passed to [`Storeys::new`],
+    /// regardless of the mapping.
+    pub async fn diagnose(&mut self) -> [LedStatus; N] {
+        let snapshots: [Snapshot; N] = core::array::from_fn(|i| self.leds[i].snapshot());
+        self.all_off();
+
+        let mut readings = [Readings::default(); N];
+        for (led, readings) in self.leds.iter_mut().zip(readings.iter_mut()) {
+            *readings = led.take_readings().await;
+        }
+
+        // Look for bridges by pulling down one LED at a time and checking whether this pulls down
+        // others as well. Pins shorted to ground are already known and would show up as bridged to
+        // every LED.
+        for n in 0..self.leds.len() {
+            self.leds[n].switch_on();
+            for led in self.leds.iter_mut() {
+                led.set_pull(Some(Pull::Up));
+            }
+            Timer::after(SETTLING_TIME).await;
+
+            for m in 0..self.leds.len() {
+                if m != n && readings[m].pulled_up && !self.leds[m].is_pin_high() {
+                    readings[n].bridged = true;
+                    readings[m].bridged = true;
+                }
+            }
+
+            for led in self.leds.iter_mut() {
+                led.set_pull(None);
+            }
+            self.leds[n].switch_off();
+        }
+
+        for (led, snapshot) in self.leds.iter_mut().zip(snapshots) {
+            led.restore(snapshot);
         }
+        readings.map(classify)
     }
 }
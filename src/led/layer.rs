@@ -0,0 +1,229 @@
+//! Compositing frames from a stack of layers.
+
+use embassy_time::{Duration, Instant};
+use heapless::Vec;
+
+use super::STOREY_LEDS;
+use crate::frame::Frame;
+
+/// How a [`Layer`] combines with the layers below it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Blend {
+    /// The layer covers the layers below it.
+    #[default]
+    Replace,
+    /// The LEDs switched on in the layer are added to the layers below it.
+    Or,
+    /// The LEDs switched on in the layer toggle the layers below it.
+    Xor,
+}
+
+/// A frame on a [`LayerStack`].
+///
+/// A layer affects only the LEDs in its mask. All other LEDs show the layers below it. Layers can
+/// expire at some point in time, like for showing a notification for a while.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layer<const N: usize = STOREY_LEDS> {
+    frame: Frame<N>,
+    mask: Frame<N>,
+    blend: Blend,
+    priority: u8,
+    expiry: Option<Instant>,
+}
+
+impl<const N: usize> Layer<N> {
+    /// Creates a layer showing `frame` on all LEDs with [`Blend::Replace`] and priority 0. It does
+    /// not expire.
+    pub const fn new(frame: Frame<N>) -> Self {
+        Self {
+            frame,
+            mask: Frame::FULL,
+            blend: Blend::Replace,
+            priority: 0,
+            expiry: None,
+        }
+    }
+
+    /// Restricts the layer to the LEDs switched on in `mask`.
+    pub const fn with_mask(mut self, mask: Frame<N>) -> Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Sets how the layer combines with the layers below it.
+    pub const fn with_blend(mut self, blend: Blend) -> Self {
+        self.blend = blend;
+        self
+    }
+
+    /// Sets the priority of the layer. Layers with a higher priority go on top of the ones with a
+    /// lower priority.
+    pub const fn with_priority(mut self, priority: u8) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Lets the layer expire at `expiry`.
+    pub const fn with_expiry(mut self, expiry: Instant) -> Self {
+        self.expiry = Some(expiry);
+        self
+    }
+
+    /// Lets the layer expire `timeout` from now.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        self.with_expiry(Instant::now() + timeout)
+    }
+
+    /// Returns the frame of the layer.
+    pub const fn frame(&self) -> Frame<N> {
+        self.frame
+    }
+
+    /// Changes the frame of the layer, like for animating it.
+    pub fn set_frame(&mut self, frame: Frame<N>) {
+        self.frame = frame;
+    }
+
+    /// Returns the priority of the layer.
+    pub const fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Returns when the layer expires, if ever.
+    pub const fn expiry(&self) -> Option<Instant> {
+        self.expiry
+    }
+
+    /// Returns whether the layer has expired at `now`.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        self.expiry.is_some_and(|expiry| expiry <= now)
+    }
+
+    /// Returns the result of putting this layer on top of `below`.
+    pub fn apply(&self, below: Frame<N>) -> Frame<N> {
+        let blended = match self.blend {
+            Blend::Replace => self.frame,
+            Blend::Or => below | self.frame,
+            Blend::Xor => below ^ self.frame,
+        };
+        (blended & self.mask) | (below & !self.mask)
+    }
+}
+
+/// Identifies a [`Layer`] on a [`LayerStack`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LayerId(u32);
+
+/// A stack of up to `L` [`Layer`]s rendering into a single frame.
+///
+/// Layers are rendered from the bottom to the top, ordered by their priority. Layers with the
+/// same priority are stacked in the order they were pushed. A background animation stays on its
+/// layer below short notifications. It comes back unchanged when they are removed or expire:
+///
+/// ```rust
+/// use embassy_time::Instant;
+/// use hakkaa::frame::Frame;
+/// use hakkaa::led::{Blend, Layer, LayerStack};
+///
+/// let mut layers: LayerStack = LayerStack::new();
+/// layers.push(Layer::new(Frame::from_art("#.#.#.#."))).unwrap();
+/// let notification = Layer::new(Frame::from_art("######.."))
+///     .with_mask(Frame::from_art("####...."))
+///     .with_blend(Blend::Xor)
+///     .with_priority(1)
+///     .with_expiry(Instant::from_millis(500));
+/// layers.push(notification).unwrap();
+/// assert_eq!(layers.render().to_string(), ".#.##.#.");
+///
+/// layers.expire(Instant::from_millis(500));
+/// assert_eq!(layers.render().to_string(), "#.#.#.#.");
+/// ```
+///
+/// [`super::Storeys::show_layers`] renders the stack onto the storey LEDs.
+#[derive(Clone, Debug)]
+pub struct LayerStack<const N: usize = STOREY_LEDS, const L: usize = 4> {
+    layers: Vec<(LayerId, Layer<N>), L>,
+    next_id: u32,
+}
+
+impl<const N: usize, const L: usize> LayerStack<N, L> {
+    /// Creates an empty stack.
+    pub const fn new() -> Self {
+        Self {
+            layers: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    /// Puts `layer` onto the stack according to its priority. Returns the layer back if the stack
+    /// is full.
+    pub fn push(&mut self, layer: Layer<N>) -> Result<LayerId, Layer<N>> {
+        let index = self
+            .layers
+            .partition_point(|(_, below)| below.priority <= layer.priority);
+        let id = LayerId(self.next_id);
+        self.layers
+            .insert(index, (id, layer))
+            .map_err(|(_, layer)| layer)?;
+        self.next_id = self.next_id.wrapping_add(1);
+        Ok(id)
+    }
+
+    /// Returns the layer `id` if it is still on the stack.
+    pub fn get(&self, id: LayerId) -> Option<&Layer<N>> {
+        self.layers
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, layer)| layer)
+    }
+
+    /// Returns the layer `id` for changing it, if it is still on the stack.
+    pub fn get_mut(&mut self, id: LayerId) -> Option<&mut Layer<N>> {
+        self.layers
+            .iter_mut()
+            .find(|(other, _)| *other == id)
+            .map(|(_, layer)| layer)
+    }
+
+    /// Removes the layer `id` from the stack and returns it.
+    pub fn remove(&mut self, id: LayerId) -> Option<Layer<N>> {
+        let index = self.layers.iter().position(|(other, _)| *other == id)?;
+        Some(self.layers.remove(index).1)
+    }
+
+    /// Removes all layers which have expired at `now`.
+    pub fn expire(&mut self, now: Instant) {
+        self.layers.retain(|(_, layer)| !layer.is_expired(now));
+    }
+
+    /// Returns when the next layer expires. The frame changes then.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.layers
+            .iter()
+            .filter_map(|(_, layer)| layer.expiry)
+            .min()
+    }
+
+    /// Returns the number of layers on the stack.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns whether the stack has no layers.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Renders all layers on top of each other, starting with all LEDs switched off.
+    pub fn render(&self) -> Frame<N> {
+        self.layers
+            .iter()
+            .fold(Frame::EMPTY, |below, (_, layer)| layer.apply(below))
+    }
+}
+
+impl<const N: usize, const L: usize> Default for LayerStack<N, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
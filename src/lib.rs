@@ -3,7 +3,7 @@
 //! There is some initializaton magic going on behing the scenes which you can call into action
 //! with a simple wink from your magic wand:
 //!
-//! ```rust
+//! ```rust,ignore
 //! let board = Board::init();
 //! ```
 //!
@@ -12,6 +12,31 @@
 
 #![no_std]
 
+#[cfg(all(feature = "esp32c3", feature = "mock"))]
+compile_error!("The feature `mock` is for host tests and can't be used with the board support.");
+
+pub mod animation;
+pub mod assets;
+#[cfg(feature = "esp32c3")]
 pub mod board;
+pub mod diag;
+#[cfg(feature = "esp32c3")]
+pub mod dim;
+pub mod easing;
+pub mod effects;
+pub mod frame;
+pub mod gamma;
 pub mod led;
+pub mod mapping;
+pub mod meter;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod morse;
+pub mod number;
+pub mod power;
+pub mod pwm;
+pub mod random;
+pub mod service;
+pub mod status;
 pub mod switch;
+pub mod transition;
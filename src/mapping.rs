@@ -0,0 +1,232 @@
+//! Mapping logical LEDs to physical ones.
+//!
+//! Frames and patterns are designed with the LED at index 0 at the bottom. Which switch drives this
+//! LED depends on how the LEDs are wired and how the board is held. A [`Mapping`] is a permutation
+//! table from the logical LED index used by frames to the physical index into the switches passed
+//! to [`crate::led::Storeys::new`]. Mappings are built at compile time:
+//!
+//! ```rust
+//! use hakkaa::frame::Frame;
+//! use hakkaa::mapping::Mapping;
+//!
+//! // The first two LEDs are swapped on this board.
+//! const WIRING: Mapping = Mapping::new([1, 0, 2, 3, 4, 5, 6, 7]);
+//! const UPSIDE_DOWN: Mapping = WIRING.mirrored();
+//!
+//! assert_eq!(WIRING.map(Frame::from_art("#.......")).to_string(), ".#......");
+//! assert_eq!(UPSIDE_DOWN.map(Frame::from_art("#.......")).to_string(), ".......#");
+//! assert_eq!(UPSIDE_DOWN.map(Frame::from_art(".#......")).to_string(), "......#.");
+//! ```
+//!
+//! An invalid table is a compile error for a constant and a panic at runtime. Use
+//! [`Mapping::try_new`] for tables from elsewhere. [`crate::led::Storeys::with_mapping`] and
+//! [`crate::led::Storeys::set_mapping`] apply a mapping to the storey LEDs.
+//!
+//! For persistence of vision, the [`Orientation`] of the board also affects the order of the
+//! columns of a pattern.
+
+use crate::frame::Frame;
+use crate::led::STOREY_LEDS;
+
+/// A permutation of `N` LEDs from logical to physical indices.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Mapping<const N: usize = STOREY_LEDS> {
+    physical: [u8; N],
+    logical: [u8; N],
+}
+
+impl<const N: usize> Mapping<N> {
+    /// Maps each LED to itself.
+    pub const IDENTITY: Self = {
+        assert!(N <= 64, "a mapping supports up to 64 LEDs");
+        let mut table = [0; N];
+        let mut index = 0;
+        while index < N {
+            table[index] = index as u8;
+            index += 1;
+        }
+        Self {
+            physical: table,
+            logical: table,
+        }
+    };
+
+    /// Creates a mapping from a table with the physical index at each logical index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `table` is not a permutation of `0..N`.
+    pub const fn new(table: [u8; N]) -> Self {
+        match Self::try_new(table) {
+            Some(mapping) => mapping,
+            None => panic!("a mapping needs each index from 0 to N - 1 exactly once"),
+        }
+    }
+
+    /// Creates a mapping from a table with the physical index at each logical index. Returns
+    /// `None` if `table` is not a permutation of `0..N`.
+    pub const fn try_new(table: [u8; N]) -> Option<Self> {
+        let mut logical = [0; N];
+        let mut seen = 0u64;
+        let mut index = 0;
+        while index < N {
+            let physical = table[index] as usize;
+            if physical >= N || seen & (1 << physical) != 0 {
+                return None;
+            }
+            seen |= 1 << physical;
+            logical[physical] = index as u8;
+            index += 1;
+        }
+        Some(Self {
+            physical: table,
+            logical,
+        })
+    }
+
+    /// Returns this mapping with the order of the LEDs reversed. The logical LED at index 0 goes to
+    /// where the one at index `N - 1` used to be.
+    pub const fn mirrored(self) -> Self {
+        let mut table = [0; N];
+        let mut index = 0;
+        while index < N {
+            table[index] = self.physical[N - 1 - index];
+            index += 1;
+        }
+        Self::new(table)
+    }
+
+    /// Returns the mapping applying this mapping first and then `next`.
+    pub const fn then(self, next: Self) -> Self {
+        let mut table = [0; N];
+        let mut index = 0;
+        while index < N {
+            table[index] = next.physical[self.physical[index] as usize];
+            index += 1;
+        }
+        Self::new(table)
+    }
+
+    /// Returns the physical index for the logical index `logical`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `logical` is out of range.
+    pub const fn physical(&self, logical: usize) -> usize {
+        self.physical[logical] as usize
+    }
+
+    /// Returns the logical index for the physical index `physical`. This is the counterpart to
+    /// [`Mapping::physical`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `physical` is out of range.
+    pub const fn logical(&self, physical: usize) -> usize {
+        self.logical[physical] as usize
+    }
+
+    /// Maps a logical frame to the physical one.
+    pub const fn map(&self, frame: Frame<N>) -> Frame<N> {
+        let mut mapped = Frame::EMPTY;
+        let mut index = 0;
+        while index < N {
+            mapped = mapped.with(self.physical(index), frame.get(index));
+            index += 1;
+        }
+        mapped
+    }
+
+    /// Maps a physical frame back to the logical one. This is the counterpart to [`Mapping::map`].
+    pub const fn unmap(&self, frame: Frame<N>) -> Frame<N> {
+        let mut unmapped = Frame::EMPTY;
+        let mut index = 0;
+        while index < N {
+            unmapped = unmapped.with(self.logical(index), frame.get(index));
+            index += 1;
+        }
+        unmapped
+    }
+}
+
+impl<const N: usize> Default for Mapping<N> {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// How the board is held or mounted compared to how its patterns are designed.
+///
+/// Turning the board upside down reverses the order of its LEDs. For persistence of vision, it
+/// also reverses the order of the columns of a pattern as they get swept through the air. Looking
+/// at the board from its back, like when it is worn facing the wearer, only reverses the columns.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    /// As designed, with the LED at index 0 at the bottom.
+    #[default]
+    Upright,
+    /// Turned by half a turn.
+    UpsideDown,
+    /// Seen from the back.
+    Mirrored,
+    /// Turned by half a turn and seen from the back.
+    MirroredUpsideDown,
+}
+
+impl Orientation {
+    /// Returns the mapping of the LEDs for this orientation on top of the `wiring` of the board.
+    pub const fn mapping<const N: usize>(self, wiring: Mapping<N>) -> Mapping<N> {
+        match self {
+            Self::Upright | Self::Mirrored => wiring,
+            Self::UpsideDown | Self::MirroredUpsideDown => wiring.mirrored(),
+        }
+    }
+
+    /// Returns whether the columns of a pattern need to be shown in reverse order.
+    pub const fn reverses_columns(self) -> bool {
+        matches!(self, Self::UpsideDown | Self::Mirrored)
+    }
+
+    /// Returns the `columns` of a pattern in the order for this orientation.
+    pub fn columns<I: DoubleEndedIterator>(self, columns: I) -> Columns<I> {
+        Columns {
+            columns,
+            reversed: self.reverses_columns(),
+        }
+    }
+}
+
+/// The columns of a pattern in the order for an [`Orientation`]. See [`Orientation::columns`].
+#[derive(Clone, Debug)]
+pub struct Columns<I> {
+    columns: I,
+    reversed: bool,
+}
+
+impl<I: DoubleEndedIterator> Iterator for Columns<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.reversed {
+            self.columns.next_back()
+        } else {
+            self.columns.next()
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.columns.size_hint()
+    }
+}
+
+impl<I: DoubleEndedIterator> DoubleEndedIterator for Columns<I> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.reversed {
+            self.columns.next()
+        } else {
+            self.columns.next_back()
+        }
+    }
+}
+
+impl<I: DoubleEndedIterator + ExactSizeIterator> ExactSizeIterator for Columns<I> {}
@@ -0,0 +1,152 @@
+//! Showing values on the storey LEDs like on a bar graph.
+//!
+//! A [`Meter`] maps a value from a range onto `N` LEDs. This is handy for showing sensor values,
+//! the progress of some work, or a countdown:
+//!
+//! ```rust
+//! use hakkaa::frame::Frame;
+//! use hakkaa::meter::{Meter, Style};
+//!
+//! let bar: Frame = Meter::default().frame(30.0, 0.0, 80.0);
+//! let dot: Frame = Meter::new(Style::Dot).frame(80.0, 0.0, 80.0);
+//! let centre: Frame = Meter::new(Style::CentreOut).frame(50.0, 0.0, 100.0);
+//!
+//! assert_eq!(bar.to_string(), "###.....");
+//! assert_eq!(dot.to_string(), ".......#");
+//! assert_eq!(centre.to_string(), "..####..");
+//! ```
+//!
+//! The LEDs can only show a value in steps of a whole LED. Dimmable LEDs can do better by
+//! partially lighting the LED at the end of the bar with [`Meter::levels`]. The storey LEDs show
+//! values with [`crate::led::Storeys::show_level`] and friends.
+
+use crate::frame::Frame;
+use crate::gamma::{ln, Level};
+
+/// How a [`Meter`] shows a value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Style {
+    /// A bar growing from the LED at index 0 towards the LED at index `N - 1`.
+    #[default]
+    Bar,
+    /// A single LED moving from index 0 for the lowest value to index `N - 1` for the highest one.
+    Dot,
+    /// A bar growing from the centre out to both ends.
+    CentreOut,
+}
+
+/// How a [`Meter`] maps values to LEDs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Scale {
+    /// Each LED stands for the same difference in value.
+    #[default]
+    Linear,
+    /// Each LED stands for the same ratio in value, like decibels. This needs a positive minimum
+    /// below a finite maximum. For other ranges, values at or above the maximum show as the
+    /// maximum and all others as the minimum.
+    Logarithmic,
+}
+
+/// Maps values onto a row of LEDs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Meter {
+    style: Style,
+    scale: Scale,
+}
+
+impl Meter {
+    /// Creates a meter with the given style and a linear scale.
+    pub fn new(style: Style) -> Self {
+        Self {
+            style,
+            scale: Scale::Linear,
+        }
+    }
+
+    /// Sets the scale for mapping values to LEDs.
+    pub fn with_scale(mut self, scale: Scale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Returns where `value` is between `min` and `max`, from 0.0 to 1.0. Values outside the range
+    /// are clamped.
+    pub fn fraction(&self, value: f32, min: f32, max: f32) -> f32 {
+        let fraction = match self.scale {
+            Scale::Linear => (value - min) / (max - min),
+            Scale::Logarithmic if min > 0.0 && max > min && max.is_finite() => {
+                // Clamping first keeps zero, negative and infinite values away from the logarithm.
+                let value = f64::from(value.clamp(min, max));
+                let (min, max) = (f64::from(min), f64::from(max));
+                (ln(value / min) / ln(max / min)) as f32
+            }
+            Scale::Logarithmic => f32::NAN,
+        };
+
+        // An empty or invalid range shows everything at or above its maximum as the maximum.
+        if fraction.is_nan() {
+            if value >= max {
+                1.0
+            } else {
+                0.0
+            }
+        } else {
+            fraction.clamp(0.0, 1.0)
+        }
+    }
+
+    /// Returns the frame showing `value` between `min` and `max` on `N` LEDs. The value is rounded
+    /// to the nearest LED.
+    pub fn frame<const N: usize>(&self, value: f32, min: f32, max: f32) -> Frame<N> {
+        let levels: [Level; N] = self.levels(value, min, max);
+        let mut frame = Frame::EMPTY;
+        for (index, level) in levels.iter().enumerate() {
+            frame.set(index, *level >= Level(128));
+        }
+        frame
+    }
+
+    /// Returns the brightness levels showing `value` between `min` and `max` on `N` dimmable LEDs.
+    /// The LED at the end of a bar is partially lit for showing the value in between two LEDs. The
+    /// dot fades over from one LED to the next one.
+    pub fn levels<const N: usize>(&self, value: f32, min: f32, max: f32) -> [Level; N] {
+        let fraction = self.fraction(value, min, max);
+        let mut levels = [Level::OFF; N];
+
+        match self.style {
+            Style::Bar => fill(&mut levels, fraction, N, |index| index),
+            Style::Dot if N > 0 => {
+                let position = fraction * (N - 1) as f32;
+                let index = position as usize;
+                let next = Level::from(position - index as f32);
+                levels[index] = Level(u8::MAX - next.0);
+                if index + 1 < N {
+                    levels[index + 1] = next;
+                }
+            }
+            Style::Dot => {}
+            Style::CentreOut => {
+                // Both halves share the centre LED for an odd number of LEDs.
+                let half = N.div_ceil(2);
+                fill(&mut levels, fraction, half, |index| N / 2 + index);
+                fill(&mut levels, fraction, half, |index| (N - 1) / 2 - index);
+            }
+        }
+
+        levels
+    }
+}
+
+/// Lights a bar of `fraction` of `leds` LEDs. The LEDs of the bar are mapped to indices into
+/// `levels` by `index`.
+fn fill(levels: &mut [Level], fraction: f32, leds: usize, index: impl Fn(usize) -> usize) {
+    let length = fraction * leds as f32;
+    let full = length as usize;
+
+    for led in 0..full.min(leds) {
+        levels[index(led)] = Level::FULL;
+    }
+    if full < leds {
+        levels[index(full)] = Level::from(length - full as f32);
+    }
+}
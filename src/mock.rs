@@ -0,0 +1,248 @@
+//! Mock LEDs for testing LED code on the host.
+//!
+//! [`MockSwitch`] can be used wherever a [`Switch`] or a [`Dimmable`] is expected, for example for
+//! building [`crate::led::Storeys`] from. It records every change together with the
+//! [`Instant`] it happened at. The recordings can be inspected through [`Recorder`]s while the
+//! switch is in use.
+//!
+//! Time is simulated with the mock driver from embassy-time. A [`Simulation`] runs async LED code
+//! like [`crate::led::Storeys::cycle`] for a given amount of simulated time:
+//!
+//! ```rust
+//! use embassy_time::{Duration, Instant};
+//! use hakkaa::led::Storeys;
+//! use hakkaa::mock::{self, Simulation};
+//!
+//! let simulation = Simulation::new();
+//! let (switches, recorders) = mock::switches::<8>();
+//! let mut storeys = Storeys::new(switches);
+//!
+//! simulation.run_for(storeys.cycle(Duration::from_millis(100)), Duration::from_millis(250));
+//!
+//! assert!(recorders[2].transitions().last().unwrap().is_on());
+//! assert_eq!(recorders[2].transitions().last().unwrap().at, Instant::from_millis(200));
+//! ```
+//!
+//! Animations can be previewed as text with [`preview`], one line per step.
+//!
+//! This module is available with the feature `mock`. It requires the standard library and is
+//! meant for host tests only.
+
+extern crate std;
+
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use std::cell::RefCell;
+use std::format;
+use std::rc::Rc;
+use std::string::{String, ToString};
+use std::sync::{Mutex, MutexGuard};
+use std::vec::Vec;
+
+use embassy_time::{Duration, Instant, MockDriver};
+
+use crate::animation::Animation;
+use crate::frame::Frame;
+use crate::gamma::Level;
+use crate::pwm::FULL_DUTY;
+use crate::switch::{Dimmable, Switch};
+
+/// A change of a [`MockSwitch`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    /// The point in (simulated) time of the change.
+    pub at: Instant,
+    /// The brightness after the change. Switching on and off results in full brightness and zero.
+    pub brightness: u8,
+}
+
+impl Transition {
+    /// Returns whether the switch was on after the change.
+    pub fn is_on(&self) -> bool {
+        self.brightness > 0
+    }
+}
+
+/// Handle for inspecting the transitions recorded by a [`MockSwitch`].
+#[derive(Clone, Debug, Default)]
+pub struct Recorder {
+    transitions: Rc<RefCell<Vec<Transition>>>,
+}
+
+impl Recorder {
+    /// Returns all transitions recorded so far.
+    pub fn transitions(&self) -> Vec<Transition> {
+        self.transitions.borrow().clone()
+    }
+
+    /// Forgets all transitions recorded so far.
+    pub fn clear(&self) {
+        self.transitions.borrow_mut().clear();
+    }
+}
+
+/// A switch recording its transitions instead of driving real hardware.
+///
+/// Only actual changes get recorded. Switching on a switch which is already on does not record
+/// anything.
+#[derive(Debug, Default)]
+pub struct MockSwitch {
+    brightness: u8,
+    recorder: Recorder,
+}
+
+impl MockSwitch {
+    /// Creates a new switch which is switched off.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a handle for inspecting the transitions of this switch.
+    pub fn recorder(&self) -> Recorder {
+        self.recorder.clone()
+    }
+}
+
+impl Switch for MockSwitch {
+    fn switch_on(&mut self) {
+        self.set_brightness(FULL_DUTY);
+    }
+
+    fn switch_off(&mut self) {
+        self.set_brightness(0);
+    }
+
+    fn is_on(&self) -> bool {
+        self.brightness > 0
+    }
+}
+
+impl Dimmable for MockSwitch {
+    fn set_brightness(&mut self, brightness: u8) {
+        if brightness != self.brightness {
+            self.brightness = brightness;
+            self.recorder.transitions.borrow_mut().push(Transition {
+                at: Instant::now(),
+                brightness,
+            });
+        }
+    }
+
+    fn brightness(&self) -> u8 {
+        self.brightness
+    }
+}
+
+/// Creates `N` mock switches along with their recorders.
+pub fn switches<const N: usize>() -> ([MockSwitch; N], [Recorder; N]) {
+    let switches: [MockSwitch; N] = core::array::from_fn(|_| MockSwitch::new());
+    let recorders = core::array::from_fn(|n| switches[n].recorder());
+    (switches, recorders)
+}
+
+/// The mock driver is global. Simulations need to take turns when tests run in parallel.
+static SIMULATION: Mutex<()> = Mutex::new(());
+
+/// Runs async code in simulated time.
+///
+/// Simulated time starts at zero for each simulation. There can only be one simulation at a time;
+/// creating another one waits for the current one to be dropped.
+pub struct Simulation {
+    resolution: Duration,
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl Simulation {
+    /// The default step size for advancing the simulated time.
+    pub const DEFAULT_RESOLUTION: Duration = Duration::from_millis(1);
+
+    /// Creates a new simulation starting at zero.
+    pub fn new() -> Self {
+        // A panicking test leaves the mutex poisoned, but the simulation gets reset anyway.
+        let guard = SIMULATION
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        MockDriver::get().reset();
+
+        Self {
+            resolution: Self::DEFAULT_RESOLUTION,
+            _guard: guard,
+        }
+    }
+
+    /// Sets the step size for advancing the simulated time. Timers expire at the end of the step
+    /// they fall into.
+    pub fn with_resolution(mut self, resolution: Duration) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Runs `future` until it completes or until `duration` of simulated time has passed. Returns
+    /// the output of the future if it completed.
+    pub fn run_for<F: Future>(&self, future: F, duration: Duration) -> Option<F::Output> {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        let end = Instant::now() + duration;
+
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return Some(output);
+            }
+
+            let now = Instant::now();
+            if now >= end {
+                return None;
+            }
+            MockDriver::get().advance(self.resolution.min(end - now));
+        }
+    }
+
+    /// Advances the simulated time without running anything.
+    pub fn advance(&self, duration: Duration) {
+        MockDriver::get().advance(duration);
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Frames which can be rendered as text.
+pub trait Render {
+    /// Renders the frame with one character per LED.
+    fn render(&self) -> String;
+}
+
+impl<const N: usize> Render for Frame<N> {
+    /// Renders the frame as ASCII art like `"#..#..##"`.
+    fn render(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl<const N: usize> Render for [Level; N] {
+    /// Renders the levels with `.` for off, `#` for full brightness, and `-`, `+` and `*` for the
+    /// thirds in between.
+    fn render(&self) -> String {
+        self.iter()
+            .map(|level| match level.0 {
+                0 => '.',
+                1..=85 => '-',
+                86..=170 => '+',
+                171..=254 => '*',
+                255 => '#',
+            })
+            .collect()
+    }
+}
+
+/// Renders an animation as text for previewing it. Each step becomes a line with the rendered
+/// frame and the duration in milliseconds like `"#..#..## 100 ms"`. The animation must end.
+pub fn preview<F: Render>(animation: impl Animation<F>) -> Vec<String> {
+    animation
+        .map(|step| format!("{} {} ms", step.frame.render(), step.duration.as_millis()))
+        .collect()
+}
@@ -0,0 +1,237 @@
+//! Morse code for getting text out of a board without a serial connection.
+//!
+//! [`encode`] turns text into an [`Animation`] switching a single LED on and off with the timing of
+//! the ITU. A dot lasts one unit and a dash three. The elements of a character are one unit apart,
+//! characters three units, and words seven:
+//!
+//! ```rust
+//! use hakkaa::morse::{self, Timing};
+//!
+//! // At 12 words per minute, a unit is 100 ms.
+//! let steps: Vec<(bool, u64)> = morse::encode("E T", Timing::new(12))
+//!     .map(|step| (step.frame, step.duration.as_millis()))
+//!     .collect();
+//! assert_eq!(steps, [(true, 100), (false, 700), (true, 300)]);
+//! ```
+//!
+//! The text plays on any [`Switch`] like the LED on the ESP32-C3 board with [`play`] and on the
+//! storey LEDs by turning it into frames with [`Morse::frames`]:
+//!
+//! ```rust,ignore
+//! let timing = Timing::new(15).with_farnsworth(8);
+//! morse::play(&mut board.esp_led, morse::encode("SOS", timing)).await;
+//! storeys.play(morse::encode("SOS", timing).frames(Frame::FULL)).await;
+//! ```
+//!
+//! Letters are case-insensitive. Characters without a Morse code are left out.
+
+use core::str::Chars;
+
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::animation::{Animation, Step};
+use crate::frame::Frame;
+use crate::switch::Switch;
+
+/// The speed of Morse code.
+///
+/// The speed is measured in words per minute of the word "PARIS", which takes 50 units. With
+/// Farnsworth spacing, the characters are sent at the full speed but spaced further apart for a
+/// slower overall speed. This helps learning to recognize characters by their sound or rhythm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timing {
+    wpm: u32,
+    farnsworth_wpm: u32,
+}
+
+impl Timing {
+    /// Creates the timing for sending `wpm` words per minute. The speed is at least one word per
+    /// minute.
+    pub const fn new(wpm: u32) -> Self {
+        let wpm = if wpm == 0 { 1 } else { wpm };
+        Self {
+            wpm,
+            farnsworth_wpm: wpm,
+        }
+    }
+
+    /// Stretches the gaps between characters and words for an overall speed of `wpm` words per
+    /// minute. Speeds at or above the character speed leave the gaps as they are.
+    pub const fn with_farnsworth(mut self, wpm: u32) -> Self {
+        self.farnsworth_wpm = if wpm == 0 { 1 } else { wpm };
+        self
+    }
+
+    /// Returns the duration of a dot.
+    pub const fn unit(&self) -> Duration {
+        Duration::from_micros(1_200_000 / self.wpm as u64)
+    }
+
+    /// Returns the gap between characters.
+    pub const fn char_gap(&self) -> Duration {
+        self.spacing(3)
+    }
+
+    /// Returns the gap between words.
+    pub const fn word_gap(&self) -> Duration {
+        self.spacing(7)
+    }
+
+    /// Returns a gap of `units` units, stretched for Farnsworth spacing.
+    const fn spacing(&self, units: u64) -> Duration {
+        let (c, s) = (self.wpm as u64, self.farnsworth_wpm as u64);
+        if s >= c {
+            return Duration::from_micros(units * 1_200_000 / c);
+        }
+
+        // The 19 units of gaps in "PARIS " take up all time left from sending its 31 units of
+        // elements at the character speed (ARRL, "A Standard for Morse Timing Using the
+        // Farnsworth Technique").
+        let gaps = (60_000_000 * c - 37_200_000 * s) / (s * c);
+        Duration::from_micros(units * gaps / 19)
+    }
+}
+
+/// Returns the Morse code of `c` as dots and dashes, like `".-"` for `'A'`. Returns `None` for
+/// characters without a code.
+pub const fn code(c: char) -> Option<&'static str> {
+    let code = match c.to_ascii_uppercase() {
+        'A' => ".-",
+        'B' => "-...",
+        'C' => "-.-.",
+        'D' => "-..",
+        'E' => ".",
+        'F' => "..-.",
+        'G' => "--.",
+        'H' => "....",
+        'I' => "..",
+        'J' => ".---",
+        'K' => "-.-",
+        'L' => ".-..",
+        'M' => "--",
+        'N' => "-.",
+        'O' => "---",
+        'P' => ".--.",
+        'Q' => "--.-",
+        'R' => ".-.",
+        'S' => "...",
+        'T' => "-",
+        'U' => "..-",
+        'V' => "...-",
+        'W' => ".--",
+        'X' => "-..-",
+        'Y' => "-.--",
+        'Z' => "--..",
+        '0' => "-----",
+        '1' => ".----",
+        '2' => "..---",
+        '3' => "...--",
+        '4' => "....-",
+        '5' => ".....",
+        '6' => "-....",
+        '7' => "--...",
+        '8' => "---..",
+        '9' => "----.",
+        '.' => ".-.-.-",
+        ',' => "--..--",
+        ':' => "---...",
+        '?' => "..--..",
+        '\'' => ".----.",
+        '-' => "-....-",
+        '/' => "-..-.",
+        '(' => "-.--.",
+        ')' => "-.--.-",
+        '"' => ".-..-.",
+        '=' => "-...-",
+        '+' => ".-.-.",
+        '@' => ".--.-.",
+        _ => return None,
+    };
+    Some(code)
+}
+
+/// Returns `text` in Morse code with `timing`. See [`Morse`].
+pub fn encode(text: &str, timing: Timing) -> Morse<'_> {
+    Morse {
+        chars: text.chars(),
+        timing,
+        code: &[],
+        element: 0,
+        gap: None,
+    }
+}
+
+/// Text in Morse code, created by [`encode`].
+///
+/// The steps switch the LED on for each dot and dash and off in between. The first step switches
+/// it on and so does the last one. Whitespace between words results in a single gap between
+/// words, regardless of how much there is.
+#[derive(Clone, Debug)]
+pub struct Morse<'a> {
+    chars: Chars<'a>,
+    timing: Timing,
+    /// The dots and dashes of the current character.
+    code: &'static [u8],
+    /// The index of the next element in `code`.
+    element: usize,
+    /// The gap before the next element. There is none before the first element.
+    gap: Option<Duration>,
+}
+
+impl<'a> Morse<'a> {
+    /// Shows `frame` for the dots and dashes and switches all LEDs off in between.
+    pub fn frames<const N: usize>(self, frame: Frame<N>) -> impl Animation<Frame<N>> + Clone + 'a {
+        self.map(move |step| {
+            let frame = if step.frame { frame } else { Frame::EMPTY };
+            Step::new(frame, step.duration)
+        })
+    }
+
+    /// Widens the pending gap to at least `gap`.
+    fn widen_gap(&mut self, gap: Duration) {
+        if let Some(pending) = &mut self.gap {
+            *pending = (*pending).max(gap);
+        }
+    }
+}
+
+impl Iterator for Morse<'_> {
+    type Item = Step<bool>;
+
+    fn next(&mut self) -> Option<Step<bool>> {
+        loop {
+            if let Some(element) = self.code.get(self.element) {
+                if let Some(gap) = self.gap.take() {
+                    return Some(Step::new(false, gap));
+                }
+
+                self.element += 1;
+                self.gap = Some(self.timing.unit());
+                let units = if *element == b'-' { 3 } else { 1 };
+                return Some(Step::new(true, self.timing.unit() * units));
+            }
+
+            let c = self.chars.next()?;
+            if c.is_whitespace() {
+                self.widen_gap(self.timing.word_gap());
+            } else if let Some(code) = code(c) {
+                self.widen_gap(self.timing.char_gap());
+                self.code = code.as_bytes();
+                self.element = 0;
+            }
+        }
+    }
+}
+
+/// Plays `morse` on `switch` and switches it off afterwards. The frames of the steps tell whether
+/// the switch is on, like the ones from [`encode`].
+pub async fn play(switch: &mut impl Switch, morse: impl Animation<bool>) {
+    let mut deadline = Instant::now();
+
+    for step in morse {
+        switch.switch(step.frame);
+        deadline += step.duration;
+        Timer::at(deadline).await;
+    }
+    switch.switch_off();
+}
@@ -0,0 +1,153 @@
+//! Showing numbers on the storey LEDs.
+//!
+//! The eight storey LEDs show a byte at a time. The bit at index _n_ goes to the LED at index _n_,
+//! so D1 shows the least significant bit. The frames for a single byte are created directly:
+//!
+//! ```rust
+//! use hakkaa::number;
+//!
+//! assert_eq!(number::binary(0b0000_0110).to_string(), ".##.....");
+//! assert_eq!(number::bcd(42).unwrap().to_string(), ".#....#.");
+//! assert_eq!(number::gray(5).to_string(), "###.....");
+//! assert_eq!(number::twos_complement(-1).to_string(), "########");
+//! ```
+//!
+//! Larger numbers don't fit onto the LEDs at once. A [`Number`] splits them into pages which are
+//! shown one after another with [`crate::led::Storeys::show_number`]:
+//!
+//! ```rust
+//! use hakkaa::number::Number;
+//!
+//! let pages: Vec<_> = Number::from_u16(0x1234)
+//!     .pages::<8>()
+//!     .map(|page| page.to_string())
+//!     .collect();
+//! assert_eq!(pages, [".#..#...", "..#.##.."]);
+//! ```
+
+use crate::frame::Frame;
+
+/// Returns the frame showing `value` in binary.
+pub const fn binary(value: u8) -> Frame {
+    Frame::from_bits(value as u64)
+}
+
+/// Returns the frame showing the two decimal digits of `value` in binary coded decimal. The ones
+/// go to D1 to D4 and the tens to D5 to D8. Values above 99 don't fit and result in `None`.
+pub const fn bcd(value: u8) -> Option<Frame> {
+    if value > 99 {
+        None
+    } else {
+        Some(Frame::from_bits(Number::from_bcd(value as u32).bits))
+    }
+}
+
+/// Returns the frame showing `value` in Gray code. Counting up in Gray code changes exactly one LED
+/// at a time.
+pub const fn gray(value: u8) -> Frame {
+    Frame::from_bits(Number::from_u8(value).gray().bits)
+}
+
+/// Returns the frame showing `value` in two's complement. Negative values have the most
+/// significant bit on D8 set.
+pub const fn twos_complement(value: i8) -> Frame {
+    Frame::from_bits(Number::from_i8(value).bits)
+}
+
+/// A number to be shown in pages on the LEDs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Number {
+    bits: u64,
+    width: u32,
+}
+
+impl Number {
+    /// Creates a number from the lowest `width` bits of `bits`.
+    const fn new(bits: u64, width: u32) -> Self {
+        Self {
+            bits: bits & (u64::MAX >> (u64::BITS - width)),
+            width,
+        }
+    }
+
+    /// Creates a number shown in binary on one page of 8 bits.
+    pub const fn from_u8(value: u8) -> Self {
+        Self::new(value as u64, u8::BITS)
+    }
+
+    /// Creates a number shown in binary on two pages of 8 bits.
+    pub const fn from_u16(value: u16) -> Self {
+        Self::new(value as u64, u16::BITS)
+    }
+
+    /// Creates a number shown in binary on four pages of 8 bits.
+    pub const fn from_u32(value: u32) -> Self {
+        Self::new(value as u64, u32::BITS)
+    }
+
+    /// Creates a number shown in two's complement on one page of 8 bits.
+    pub const fn from_i8(value: i8) -> Self {
+        Self::new(value as u64, i8::BITS)
+    }
+
+    /// Creates a number shown in two's complement on two pages of 8 bits.
+    pub const fn from_i16(value: i16) -> Self {
+        Self::new(value as u64, i16::BITS)
+    }
+
+    /// Creates a number shown in two's complement on four pages of 8 bits.
+    pub const fn from_i32(value: i32) -> Self {
+        Self::new(value as u64, i32::BITS)
+    }
+
+    /// Creates a number shown in binary coded decimal with four bits per decimal digit. There are
+    /// as many digits as needed, but at least two.
+    pub const fn from_bcd(value: u32) -> Self {
+        let mut bits = 0;
+        let mut digits = 0;
+        let mut rest = value;
+        while rest > 0 || digits < 2 {
+            bits |= ((rest % 10) as u64) << (4 * digits);
+            rest /= 10;
+            digits += 1;
+        }
+        Self::new(bits, 4 * digits)
+    }
+
+    /// Converts this number to Gray code.
+    pub const fn gray(self) -> Self {
+        Self::new(self.bits ^ (self.bits >> 1), self.width)
+    }
+
+    /// Returns the bits of this number.
+    pub const fn bits(self) -> u64 {
+        self.bits
+    }
+
+    /// Returns the number of bits of this number.
+    pub const fn width(self) -> u32 {
+        self.width
+    }
+
+    /// Returns the pages for showing this number on `N` LEDs, most significant page first. The
+    /// last page is the least significant one and the most significant page is padded with zeros.
+    pub fn pages<const N: usize>(
+        self,
+    ) -> impl DoubleEndedIterator<Item = Frame<N>> + ExactSizeIterator + Clone {
+        let count = if N == 0 {
+            0
+        } else {
+            (self.width as usize).div_ceil(N).max(1)
+        };
+
+        (0..count).rev().map(move |page| {
+            let shift = page * N;
+            let bits = if shift < u64::BITS as usize {
+                self.bits >> shift
+            } else {
+                0
+            };
+            Frame::from_bits(bits)
+        })
+    }
+}
@@ -0,0 +1,182 @@
+//! Limiting the current drawn by the LEDs.
+//!
+//! Each lit LED draws some current. With all storey LEDs and the ESP LED switched on at once, the
+//! total can exceed what a weak power supply or a coin cell delivers. A [`Budget`] limits the
+//! current to a maximum by limiting how many LEDs are lit at once:
+//!
+//! ```rust
+//! use hakkaa::frame::Frame;
+//! use hakkaa::power::Budget;
+//!
+//! // Up to 20 mA with 5 mA per LED, keeping 5 mA for the ESP LED.
+//! let budget = Budget::new(20.0, 5.0).with_reserve(5.0);
+//! let all: Frame = Frame::FULL;
+//! assert_eq!(budget.max_leds(), 3);
+//! assert_eq!(budget.limit_frame(all), Frame::from_art("###....."));
+//!
+//! let multiplexed: Vec<Frame> = budget.groups(all).collect();
+//! assert_eq!(multiplexed, [
+//!     Frame::from_art("###....."),
+//!     Frame::from_art("...###.."),
+//!     Frame::from_art("......##"),
+//! ]);
+//! ```
+//!
+//! The [`Strategy`] decides what happens to the LEDs beyond the budget.
+//! [`crate::led::Storeys::with_budget`] enforces a budget on the storey LEDs and
+//! [`crate::pwm::SoftPwm::with_budget`] on LEDs dimmed by software PWM.
+//!
+//! A budget only covers the LEDs it is given to. LEDs dimmed on their own, like the ones handed out
+//! by `crate::dim::Dimming` and set through [`crate::switch::Dimmable::set_brightness`], know
+//! nothing about the other LEDs and can't share a budget with them. Build [`crate::led::Storeys`]
+//! from them for limiting their current.
+//!
+//! Dimmed LEDs draw their current in pulses. The budget for the brightness of dimmed LEDs applies
+//! to their average current. This is what matters for batteries and for power supplies with some
+//! capacitance at their output.
+
+use embassy_time::Duration;
+
+use crate::frame::Frame;
+
+/// What to do when the LEDs would draw more current than a [`Budget`] allows.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Strategy {
+    /// LEDs beyond the budget stay off. The LEDs with the lowest indices win.
+    #[default]
+    Cap,
+    /// All dimmed LEDs get darker by the same factor. LEDs which can only be switched on or off
+    /// get capped.
+    Scale,
+    /// The LEDs take turns in groups fitting into the budget, each group lit for the given slot.
+    /// Cycling through the groups fast enough makes them appear lit at the same time, but darker.
+    /// Where there is no time for taking turns, like for showing a single frame, the LEDs get
+    /// capped. Dimmed LEDs get scaled, except for software PWM which multiplexes them.
+    ///
+    /// Slots shorter than [`MIN_SLOT`] get raised to it by [`Budget::with_strategy`].
+    Multiplex(Duration),
+}
+
+/// The shortest slot for [`Strategy::Multiplex`]. Shorter slots would keep the executor busy with
+/// switching LEDs and starve all other tasks.
+pub const MIN_SLOT: Duration = Duration::from_millis(1);
+
+/// A limit for the current drawn by LEDs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Budget {
+    max_current: f32,
+    led_current: f32,
+    reserve: f32,
+    strategy: Strategy,
+}
+
+impl Budget {
+    /// Creates a budget of `max_current` for LEDs drawing `led_current` each, with
+    /// [`Strategy::Cap`]. The currents are in milliamps.
+    pub fn new(max_current: f32, led_current: f32) -> Self {
+        Self {
+            max_current,
+            led_current,
+            reserve: 0.0,
+            strategy: Strategy::Cap,
+        }
+    }
+
+    /// Keeps `reserve` of the maximum current for other loads, like the ESP LED.
+    pub fn with_reserve(mut self, reserve: f32) -> Self {
+        self.reserve = reserve;
+        self
+    }
+
+    /// Sets what to do with LEDs beyond the budget. The slot of [`Strategy::Multiplex`] is at
+    /// least [`MIN_SLOT`].
+    pub fn with_strategy(mut self, strategy: Strategy) -> Self {
+        self.strategy = match strategy {
+            Strategy::Multiplex(slot) => Strategy::Multiplex(slot.max(MIN_SLOT)),
+            strategy => strategy,
+        };
+        self
+    }
+
+    /// Returns what to do with LEDs beyond the budget.
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Returns how many fully lit LEDs the budget allows, as a fraction.
+    fn leds(&self) -> f32 {
+        let leds = (self.max_current - self.reserve) / self.led_current;
+        if leds.is_nan() {
+            0.0
+        } else {
+            leds.max(0.0)
+        }
+    }
+
+    /// Returns how many LEDs may be lit at once.
+    pub fn max_leds(&self) -> usize {
+        self.leds() as usize
+    }
+
+    /// Returns the current drawn by LEDs with the given duty cycles on average.
+    pub fn current(&self, duties: &[u8]) -> f32 {
+        let duty: u32 = duties.iter().map(|duty| u32::from(*duty)).sum();
+        duty as f32 / f32::from(u8::MAX) * self.led_current
+    }
+
+    /// Returns `frame` with the LEDs beyond the budget switched off.
+    pub fn limit_frame<const N: usize>(&self, frame: Frame<N>) -> Frame<N> {
+        let mut limited = Frame::EMPTY;
+        let lit = (0..N).filter(|index| frame[*index]);
+        for index in lit.take(self.max_leds()) {
+            limited.set(index, true);
+        }
+        limited
+    }
+
+    /// Splits `frame` into groups of LEDs fitting into the budget for multiplexing them. The groups
+    /// are ordered by the LEDs they contain. An empty frame results in a single empty group.
+    pub fn groups<const N: usize>(
+        &self,
+        frame: Frame<N>,
+    ) -> impl Iterator<Item = Frame<N>> + Clone {
+        let budget = *self;
+        let mut rest = Some(frame);
+
+        core::iter::from_fn(move || {
+            let group = budget.limit_frame(rest?);
+            let remaining = rest? & !group;
+            // Nothing fits into an empty budget. Don't take turns forever.
+            rest = (!remaining.is_off() && !group.is_off()).then_some(remaining);
+            Some(group)
+        })
+    }
+
+    /// Returns the duty cycles limited to the budget according to the strategy. Duty cycles within
+    /// the budget are returned as they are.
+    pub fn limit_duties<const N: usize>(&self, duties: [u8; N]) -> [u8; N] {
+        let available = self.leds() * f32::from(u8::MAX);
+        let total: u32 = duties.iter().map(|duty| u32::from(*duty)).sum();
+        if total as f32 <= available {
+            return duties;
+        }
+
+        match self.strategy {
+            Strategy::Cap => {
+                let mut used = 0;
+                duties.map(|duty| {
+                    if (used + u32::from(duty)) as f32 <= available {
+                        used += u32::from(duty);
+                        duty
+                    } else {
+                        0
+                    }
+                })
+            }
+            Strategy::Scale | Strategy::Multiplex(_) => {
+                let factor = available / total as f32;
+                duties.map(|duty| (f32::from(duty) * factor) as u8)
+            }
+        }
+    }
+}
@@ -0,0 +1,333 @@
+//! Software PWM for dimming LEDs connected to plain GPIOs.
+//!
+//! The LEDs on the Hakkaa board can only be switched fully on or off by their GPIOs. Switching them
+//! on and off fast enough lets them appear dimmed: An LED switched on for a quarter of the time
+//! appears with a quarter of its brightness.
+//!
+//! [`SoftPwm`] does exactly this for a set of LEDs. It switches all dimmed LEDs on at the start of
+//! each PWM period and switches them off again at the points in time given by their duty cycles.
+//! This takes at most one timer wakeup per LED and period, which is easy to keep up with for the
+//! embassy executor.
+//!
+//! The duty cycles are shared through [`Duties`] and are set with the [`PwmChannel`]s handed out by
+//! [`Duties::channels`]. These channels implement [`Dimmable`] and can be used like any other
+//! switch, for example for building [`crate::led::Storeys`] from.
+//!
+//! The actual timing for a period is computed by [`Schedule`]. It is plain data and does not
+//! depend on the hardware.
+
+use core::cell::Cell;
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_time::{Duration, Instant, Timer};
+use heapless::Vec;
+
+use crate::frame::Frame;
+use crate::gamma::{GammaTable, GAMMA};
+use crate::power::{Budget, Strategy};
+use crate::switch::{Dimmable, Switch};
+
+/// The default number of PWM periods per second. This is fast enough for not noticing any
+/// flicker, even when moving the board.
+pub const DEFAULT_REFRESH_RATE_HZ: u64 = 200;
+
+/// The duty cycle for fully switching on an LED.
+pub const FULL_DUTY: u8 = u8::MAX;
+
+/// The duty cycles for `N` LEDs driven by [`SoftPwm`].
+///
+/// This is meant to be placed into a `static` for sharing it between the task running the PWM and
+/// the [`PwmChannel`]s used for setting the duty cycles.
+#[derive(Debug)]
+pub struct Duties<const N: usize> {
+    inner: Mutex<CriticalSectionRawMutex, Cell<[u8; N]>>,
+}
+
+impl<const N: usize> Duties<N> {
+    /// Creates duty cycles with all LEDs switched off.
+    pub const fn new() -> Self {
+        Self {
+            inner: Mutex::new(Cell::new([0; N])),
+        }
+    }
+
+    /// Returns the duty cycle for the LED at `index`.
+    pub fn get(&self, index: usize) -> u8 {
+        self.inner.lock(|duties| duties.get()[index])
+    }
+
+    /// Sets the duty cycle for the LED at `index`.
+    pub fn set(&self, index: usize, duty: u8) {
+        self.inner.lock(|duties| {
+            let mut values = duties.get();
+            values[index] = duty;
+            duties.set(values);
+        });
+    }
+
+    /// Returns the duty cycles of all LEDs.
+    pub fn all(&self) -> [u8; N] {
+        self.inner.lock(|duties| duties.get())
+    }
+
+    /// Returns a channel for setting the duty cycle for each of the LEDs.
+    pub fn channels(&self) -> [PwmChannel<'_, N>; N] {
+        core::array::from_fn(|index| PwmChannel {
+            duties: self,
+            index,
+            gamma: &GAMMA,
+        })
+    }
+}
+
+impl<const N: usize> Default for Duties<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A single LED driven by [`SoftPwm`].
+#[derive(Debug)]
+pub struct PwmChannel<'a, const N: usize> {
+    duties: &'a Duties<N>,
+    index: usize,
+    gamma: &'a GammaTable,
+}
+
+impl<'a, const N: usize> PwmChannel<'a, N> {
+    /// Sets the gamma table for mapping perceptual brightness levels to duty cycles in
+    /// [`Dimmable::set_level`]. This is [`GAMMA`] by default.
+    pub fn with_gamma(mut self, gamma: &'a GammaTable) -> Self {
+        self.gamma = gamma;
+        self
+    }
+}
+
+impl<const N: usize> Switch for PwmChannel<'_, N> {
+    fn switch_on(&mut self) {
+        self.set_brightness(FULL_DUTY);
+    }
+
+    fn switch_off(&mut self) {
+        self.set_brightness(0);
+    }
+
+    fn is_on(&self) -> bool {
+        self.brightness() > 0
+    }
+}
+
+impl<const N: usize> Dimmable for PwmChannel<'_, N> {
+    fn set_brightness(&mut self, brightness: u8) {
+        self.duties.set(self.index, brightness);
+    }
+
+    fn brightness(&self) -> u8 {
+        self.duties.get(self.index)
+    }
+
+    fn gamma(&self) -> &GammaTable {
+        self.gamma
+    }
+}
+
+/// A point within a PWM period where LEDs get switched off.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge {
+    /// The point in the period in 1/255ths of the period.
+    pub at: u8,
+    /// The LEDs to switch off with bit _n_ representing the LED at index _n_.
+    pub mask: u32,
+}
+
+impl Edge {
+    /// Returns the offset of this edge from the start of a period of the given length.
+    pub fn offset(&self, period: Duration) -> Duration {
+        period * u32::from(self.at) / u32::from(FULL_DUTY)
+    }
+}
+
+/// The switching schedule for a single PWM period of up to 32 LEDs.
+///
+/// LEDs with a non-zero duty cycle are switched on at the start of the period and are switched off
+/// at their [`Edge`]. LEDs with the same duty cycle share an edge and fully switched on LEDs don't
+/// have one at all.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Schedule<const N: usize> {
+    on: u32,
+    edges: Vec<Edge, N>,
+}
+
+impl<const N: usize> Schedule<N> {
+    /// Computes the schedule for the given duty cycles.
+    pub fn new(duties: &[u8; N]) -> Self {
+        const { assert!(N <= 32, "a schedule supports up to 32 LEDs") };
+
+        let mut on = 0;
+        let mut edges: Vec<Edge, N> = Vec::new();
+
+        for (index, duty) in duties.iter().copied().enumerate() {
+            let bit = 1 << index;
+
+            if duty > 0 {
+                on |= bit;
+            }
+            if duty == 0 || duty == FULL_DUTY {
+                continue;
+            }
+
+            match edges.iter_mut().find(|edge| edge.at == duty) {
+                Some(edge) => edge.mask |= bit,
+                // There are at most as many edges as LEDs.
+                None => edges
+                    .push(Edge {
+                        at: duty,
+                        mask: bit,
+                    })
+                    .unwrap(),
+            }
+        }
+
+        edges.sort_unstable_by_key(|edge| edge.at);
+
+        Self { on, edges }
+    }
+
+    /// Returns the LEDs to switch on at the start of the period with bit _n_ representing the LED
+    /// at index _n_.
+    pub fn on(&self) -> u32 {
+        self.on
+    }
+
+    /// Returns the points for switching off LEDs in chronological order.
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+}
+
+/// Software PWM driving up to `N` LEDs with the duty cycles from [`Duties`].
+///
+/// The PWM is running while the future returned by [`SoftPwm::run`] is polled. Typically, this is
+/// done from a dedicated task.
+#[derive(Debug)]
+pub struct SoftPwm<'a, S, const N: usize> {
+    leds: Vec<(usize, S), N>,
+    duties: &'a Duties<N>,
+    period: Duration,
+    budget: Option<Budget>,
+}
+
+impl<'a, S: Switch, const N: usize> SoftPwm<'a, S, N> {
+    /// Creates a software PWM for the given LEDs running at [`DEFAULT_REFRESH_RATE_HZ`]. The LED
+    /// at index _n_ is driven with the duty cycle at index _n_.
+    pub fn new(leds: [S; N], duties: &'a Duties<N>) -> Self {
+        Self::new_partial(leds.into_iter().enumerate().collect(), duties)
+    }
+
+    /// Creates a software PWM for only some of the LEDs from `duties`. Each LED comes along with
+    /// the index of its duty cycle.
+    pub fn new_partial(leds: Vec<(usize, S), N>, duties: &'a Duties<N>) -> Self {
+        Self {
+            leds,
+            duties,
+            period: Duration::from_hz(DEFAULT_REFRESH_RATE_HZ),
+            budget: None,
+        }
+    }
+
+    /// Sets the number of PWM periods per second.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hz` is zero.
+    pub fn with_refresh_rate(mut self, hz: u64) -> Self {
+        assert!(hz > 0, "the refresh rate needs to be at least 1 Hz");
+        self.period = Duration::from_hz(hz);
+        self
+    }
+
+    /// Limits the number of LEDs driven by this PWM which are lit at the same time to `budget`.
+    ///
+    /// With [`Strategy::Cap`], the LEDs beyond the budget stay off. Otherwise, the LEDs take turns:
+    /// Each period is split into slots for groups of LEDs fitting into the budget. This makes them
+    /// darker by the number of groups. The slot of a [`Strategy::Multiplex`] is not used, the
+    /// groups always take turns within a period.
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Returns the LEDs driven by this PWM along with the index of their duty cycle.
+    pub fn free(self) -> Vec<(usize, S), N> {
+        self.leds
+    }
+
+    /// Runs the PWM.
+    ///
+    /// The PWM is running until the returned future is dropped. So `await`ing this future alone
+    /// will block forever.
+    pub async fn run(&mut self) -> ! {
+        let mut period_start = Instant::now();
+
+        loop {
+            // Duty cycles are only picked up at the start of a period to not cut a period short.
+            let duties = self.duties.all();
+            let groups = self.groups(&duties);
+            let slot = self.period / groups.len().max(1) as u32;
+
+            for (number, group) in groups.iter().enumerate() {
+                let slot_start = period_start + slot * number as u32;
+                let group_duties: [u8; N] =
+                    core::array::from_fn(|index| if group[index] { duties[index] } else { 0 });
+                let schedule = Schedule::new(&group_duties);
+
+                // The first group starts right away with the period.
+                if number > 0 {
+                    Timer::at(slot_start).await;
+                }
+                for (index, led) in self.leds.iter_mut() {
+                    led.switch(schedule.on() & 1 << *index != 0);
+                }
+
+                for edge in schedule.edges() {
+                    Timer::at(slot_start + edge.offset(slot)).await;
+
+                    for (index, led) in self.leds.iter_mut() {
+                        if edge.mask & 1 << *index != 0 {
+                            led.switch_off();
+                        }
+                    }
+                }
+            }
+
+            // Start over from now if we fell behind for more than a period. Trying to catch up
+            // would show all the missed periods in a hurry.
+            period_start += self.period;
+            let now = Instant::now();
+            if now > period_start {
+                period_start = now;
+            }
+            Timer::at(period_start).await;
+        }
+    }
+
+    /// Returns the groups of LEDs lit in turns for the given duty cycles. Without a budget, all of
+    /// them are lit at once.
+    fn groups(&self, duties: &[u8; N]) -> Vec<Frame<N>, N> {
+        let lit = self
+            .leds
+            .iter()
+            .filter(|(index, _)| duties[*index] > 0)
+            .fold(Frame::EMPTY, |lit, (index, _)| lit.with(*index, true));
+
+        // There are at most as many groups as LEDs. Without any LEDs, there is nothing to group.
+        match self.budget {
+            None => core::iter::once(lit).take(N).collect(),
+            Some(budget) if budget.strategy() == Strategy::Cap => {
+                core::iter::once(budget.limit_frame(lit)).take(N).collect()
+            }
+            Some(budget) => budget.groups(lit).take(N).collect(),
+        }
+    }
+}
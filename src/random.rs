@@ -0,0 +1,17 @@
+//! Pseudo random numbers for effects and transitions.
+//!
+//! Random effects like [`crate::effects::Sparkle`] and transitions like
+//! [`crate::transition::Kind::Dissolve`] are driven by a seed. The same seed results in the same
+//! random numbers, so they look the same every time they are played.
+
+/// The seed used unless another one is given.
+pub const DEFAULT_SEED: u32 = 0x4841_4b4b;
+
+/// Returns the next state of the Xorshift32 random number generator from George Marsaglia. It's
+/// good enough for blinking LEDs. The state must not be zero.
+pub(crate) fn xorshift(mut state: u32) -> u32 {
+    state ^= state << 13;
+    state ^= state >> 17;
+    state ^= state << 5;
+    state
+}
@@ -0,0 +1,267 @@
+//! Shared access to the LEDs from multiple tasks.
+//!
+//! [`Storeys`] are owned by a single task. An [`LedService`] owns the storey LEDs and the LED on
+//! the ESP32-C3 board and runs them in a task of its own. Any other task controls the LEDs by
+//! sending [`Command`]s through an [`LedHandle`]. Handles are cheap to copy and can be passed to as
+//! many tasks as needed:
+//!
+//! ```rust,ignore
+//! static LEDS: LedChannel = LedChannel::new();
+//!
+//! let service = LedService::new(Storeys::new(board.storey_leds), board.esp_led, &LEDS);
+//! let leds = service.handle();
+//! spawner.spawn(service::led_task(service)).unwrap();
+//!
+//! leds.set_frame(Frame::from_art("#.#.#.#.")).await;
+//! ```
+//!
+//! Commands are processed in the order they are sent. A new command for the storey LEDs replaces
+//! what they were showing before, except for [`Command::Flash`] which returns to it afterwards.
+
+use embassy_futures::select::{select, Either};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::channel::{Channel, Receiver, Sender, TrySendError};
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::animation::{KeyframeSteps, Keyframes, LoopMode, Step};
+use crate::frame::Frame;
+use crate::led::Storeys;
+use crate::switch::Switch;
+
+/// The number of commands which can be queued for an [`LedService`].
+pub const COMMAND_QUEUE: usize = 8;
+
+/// The channel for sending [`Command`]s to an [`LedService`].
+pub type LedChannel = Channel<CriticalSectionRawMutex, Command, COMMAND_QUEUE>;
+
+/// Commands for an [`LedService`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// Shows a frame on the storey LEDs.
+    SetFrame(Frame),
+    /// Plays keyframes on the storey LEDs according to their loop mode. The last frame stays on
+    /// when they end.
+    Play(Keyframes),
+    /// Stops playing and switches off all storey LEDs.
+    Stop,
+    /// Shows a frame on the storey LEDs for a while and then returns to what they were showing
+    /// before. A paused animation continues with the step it was at.
+    Flash {
+        /// The frame to show.
+        frame: Frame,
+        /// How long to show it.
+        duration: Duration,
+    },
+    /// Switches the LED on the ESP32-C3 board on or off.
+    SetEspLed(bool),
+}
+
+/// Sends [`Command`]s to an [`LedService`].
+#[derive(Clone, Copy, Debug)]
+pub struct LedHandle<'a> {
+    sender: Sender<'a, CriticalSectionRawMutex, Command, COMMAND_QUEUE>,
+}
+
+impl LedHandle<'_> {
+    /// Sends `command`, waiting for room in the queue if needed.
+    pub async fn send(&self, command: Command) {
+        self.sender.send(command).await;
+    }
+
+    /// Sends `command` if there is room in the queue. Otherwise the command is returned in the
+    /// error.
+    pub fn try_send(&self, command: Command) -> Result<(), TrySendError<Command>> {
+        self.sender.try_send(command)
+    }
+
+    /// Shows `frame` on the storey LEDs.
+    pub async fn set_frame(&self, frame: Frame) {
+        self.send(Command::SetFrame(frame)).await;
+    }
+
+    /// Plays `steps` once on the storey LEDs.
+    pub async fn play(&self, steps: &'static [Step]) {
+        self.play_keyframes(Keyframes::new(steps)).await;
+    }
+
+    /// Plays `steps` on the storey LEDs over and over again.
+    pub async fn play_forever(&self, steps: &'static [Step]) {
+        self.play_keyframes(Keyframes::new(steps).with_loop(LoopMode::Forever))
+            .await;
+    }
+
+    /// Plays `keyframes` on the storey LEDs according to their loop mode.
+    pub async fn play_keyframes(&self, keyframes: Keyframes) {
+        self.send(Command::Play(keyframes)).await;
+    }
+
+    /// Stops playing and switches off all storey LEDs.
+    pub async fn stop(&self) {
+        self.send(Command::Stop).await;
+    }
+
+    /// Shows `frame` on the storey LEDs for `duration` and returns to what they were showing
+    /// before.
+    pub async fn flash(&self, frame: Frame, duration: Duration) {
+        self.send(Command::Flash { frame, duration }).await;
+    }
+
+    /// Switches the LED on the ESP32-C3 board on or off.
+    pub async fn set_esp_led(&self, on: bool) {
+        self.send(Command::SetEspLed(on)).await;
+    }
+}
+
+/// The keyframes being played by an [`LedService`].
+#[derive(Debug)]
+struct Playing {
+    steps: KeyframeSteps,
+    /// The step currently shown.
+    step: Step,
+}
+
+/// Owns the storey LEDs and the LED on the ESP32-C3 board and controls them by the [`Command`]s
+/// received from [`LedHandle`]s.
+pub struct LedService<'a, S, E> {
+    storeys: Storeys<S>,
+    esp_led: E,
+    receiver: Receiver<'a, CriticalSectionRawMutex, Command, COMMAND_QUEUE>,
+    sender: Sender<'a, CriticalSectionRawMutex, Command, COMMAND_QUEUE>,
+    /// The frame shown when not flashing.
+    frame: Frame,
+    playing: Option<Playing>,
+    flashing: bool,
+    /// When the current step or flash ends.
+    deadline: Option<Instant>,
+}
+
+impl<'a, S: Switch, E: Switch> LedService<'a, S, E> {
+    /// Creates a service for the LEDs receiving commands through `channel`.
+    pub fn new(storeys: Storeys<S>, esp_led: E, channel: &'a LedChannel) -> Self {
+        Self {
+            frame: storeys.frame(),
+            storeys,
+            esp_led,
+            receiver: channel.receiver(),
+            sender: channel.sender(),
+            playing: None,
+            flashing: false,
+            deadline: None,
+        }
+    }
+
+    /// Returns a handle for sending commands to this service.
+    pub fn handle(&self) -> LedHandle<'a> {
+        LedHandle {
+            sender: self.sender,
+        }
+    }
+
+    /// Runs the service, processing commands forever.
+    pub async fn run(mut self) -> ! {
+        loop {
+            let command = match self.deadline {
+                Some(deadline) => {
+                    match select(Timer::at(deadline), self.receiver.receive()).await {
+                        Either::First(()) => {
+                            self.expire(deadline);
+                            continue;
+                        }
+                        Either::Second(command) => command,
+                    }
+                }
+                None => self.receiver.receive().await,
+            };
+            self.process(command);
+        }
+    }
+
+    /// Carries out `command`.
+    fn process(&mut self, command: Command) {
+        match command {
+            Command::SetFrame(frame) => {
+                self.playing = None;
+                self.show(frame);
+            }
+            Command::Play(keyframes) => {
+                let mut steps = keyframes.steps();
+                self.playing = steps.next().map(|step| Playing { steps, step });
+                self.start_step(Instant::now());
+            }
+            Command::Stop => {
+                self.playing = None;
+                self.show(Frame::EMPTY);
+            }
+            Command::Flash { frame, duration } => {
+                self.storeys.set_frame(frame);
+                self.flashing = true;
+                self.deadline = Some(Instant::now() + duration);
+            }
+            Command::SetEspLed(true) => self.esp_led.switch_on(),
+            Command::SetEspLed(false) => self.esp_led.switch_off(),
+        }
+    }
+
+    /// Ends the current flash or step which was due at `deadline`.
+    fn expire(&mut self, deadline: Instant) {
+        if self.flashing {
+            self.show(self.frame);
+            if self.playing.is_some() {
+                self.start_step(Instant::now());
+            }
+            return;
+        }
+
+        if let Some(playing) = &mut self.playing {
+            match playing.steps.next() {
+                Some(step) => playing.step = step,
+                None => self.playing = None,
+            }
+        }
+        // Start the next step right when the previous one ended. This keeps the timing from
+        // drifting.
+        self.start_step(deadline);
+    }
+
+    /// Shows `frame` without any deadline.
+    fn show(&mut self, frame: Frame) {
+        self.frame = frame;
+        self.flashing = false;
+        self.deadline = None;
+        self.storeys.set_frame(frame);
+    }
+
+    /// Shows the current step starting at `start`. Playing stops after the last one.
+    fn start_step(&mut self, start: Instant) {
+        match self.playing.as_ref().map(|playing| playing.step) {
+            Some(step) => {
+                let deadline = start + step.duration;
+                self.show(step.frame);
+                self.deadline = Some(deadline);
+            }
+            None => {
+                self.playing = None;
+                self.deadline = None;
+            }
+        }
+    }
+}
+
+/// Runs an [`LedService`] for the LEDs of the Hakkaa board.
+#[cfg(feature = "esp32c3")]
+#[embassy_executor::task]
+pub async fn led_task(
+    service: LedService<'static, crate::board::Led<'static>, crate::board::Led<'static>>,
+) -> ! {
+    service.run().await
+}
+
+/// Runs an [`LedService`] for the storey LEDs of the Hakkaa board only. The LED on the ESP32-C3
+/// board is left to a [`crate::status::StatusLed`].
+#[cfg(feature = "esp32c3")]
+#[embassy_executor::task]
+pub async fn storey_task(
+    service: LedService<'static, crate::board::Led<'static>, crate::switch::Unconnected>,
+) -> ! {
+    service.run().await
+}
@@ -0,0 +1,263 @@
+//! Reporting the health of the firmware with the LED on the ESP32-C3 board.
+//!
+//! A [`StatusLed`] blinks a single LED in a pattern telling the current [`Status`]. It runs in a
+//! task of its own, so any part of the firmware can report its status through a [`StatusHandle`]
+//! without touching the storey LEDs:
+//!
+//! ```rust,ignore
+//! static STATUS: StatusSignal = StatusSignal::new();
+//!
+//! let status_led = StatusLed::new(board.esp_led, &STATUS);
+//! let status = status_led.handle();
+//! spawner.spawn(status::status_task(status_led)).unwrap();
+//!
+//! status.heartbeat();
+//! ```
+//!
+//! An [`crate::service::LedService`] for the storey LEDs then gets an
+//! [`crate::switch::Unconnected`] switch in place of the LED on the ESP32-C3 board and runs in
+//! `service::storey_task`.
+//!
+//! Only the latest status counts. Reporting a status never waits and reporting the current status
+//! again keeps its pattern going without restarting it. A new status switches the LED off for a
+//! moment before starting its pattern, so that it can't be mistaken for part of the previous one.
+//!
+//! Error codes are shown as groups of blinks with a pause in between. The first blink of a group is
+//! a long one, marking where counting starts. Code 3 is long-short-short:
+//!
+//! ```rust
+//! use hakkaa::status::Status;
+//!
+//! let blinks: Vec<(bool, u64)> = Status::Error(3)
+//!     .blinks()
+//!     .map(|step| (step.frame, step.duration.as_millis()))
+//!     .collect();
+//! assert_eq!(blinks, [
+//!     (true, 600), (false, 300),
+//!     (true, 200), (false, 300),
+//!     (true, 200), (false, 1_500),
+//! ]);
+//! ```
+
+use embassy_futures::select::{select, Either};
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Timer};
+
+use crate::animation::Step;
+use crate::switch::Switch;
+
+/// How long the LED stays dark between repetitions of a pattern which has no pause of its own.
+const PAUSE: Duration = Duration::from_millis(1_500);
+
+/// A beat of a heartbeat, twice within two seconds.
+const BEAT: Duration = Duration::from_millis(60);
+
+/// The first blink of an error code.
+const LONG: Duration = Duration::from_millis(600);
+
+/// All other blinks of an error code.
+const SHORT: Duration = Duration::from_millis(200);
+
+/// The time between the blinks of an error code.
+const GAP: Duration = Duration::from_millis(300);
+
+static HEARTBEAT: [Step<bool>; 4] = [
+    Step::new(true, BEAT),
+    Step::new(false, Duration::from_millis(140)),
+    Step::new(true, BEAT),
+    Step::new(false, Duration::from_millis(1_740)),
+];
+
+static BUSY: [Step<bool>; 2] = [
+    Step::new(true, Duration::from_millis(100)),
+    Step::new(false, Duration::from_millis(100)),
+];
+
+static ATTENTION: [Step<bool>; 2] = [
+    Step::new(true, Duration::from_millis(800)),
+    Step::new(false, Duration::from_millis(200)),
+];
+
+/// The states shown by a [`StatusLed`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Status {
+    /// The LED stays off.
+    #[default]
+    Off,
+    /// Everything is fine. The LED beats twice every two seconds.
+    Heartbeat,
+    /// Working on something. The LED flickers quickly.
+    Busy,
+    /// Waiting for the user. The LED is lit most of the time with short interruptions.
+    Attention,
+    /// Something went wrong. The LED blinks the code in groups, see the [module
+    /// documentation](self). Codes up to 9 are easy to count. Code 0 keeps the LED off.
+    Error(u8),
+}
+
+impl Status {
+    /// Returns one repetition of the pattern for this status. The frames of the steps tell whether
+    /// the LED is on.
+    pub fn blinks(self) -> Blinks {
+        Blinks {
+            status: self,
+            index: 0,
+        }
+    }
+
+    /// Returns the step at `index` of the pattern or `None` after its end.
+    fn step(self, index: usize) -> Option<Step<bool>> {
+        match self {
+            Self::Off | Self::Error(0) => (index == 0).then_some(Step::new(false, PAUSE)),
+            Self::Heartbeat => HEARTBEAT.get(index).copied(),
+            Self::Busy => BUSY.get(index).copied(),
+            Self::Attention => ATTENTION.get(index).copied(),
+            Self::Error(code) => {
+                let code = usize::from(code);
+                let blink = index / 2;
+                if blink >= code {
+                    None
+                } else if index.is_multiple_of(2) {
+                    Some(Step::new(true, if blink == 0 { LONG } else { SHORT }))
+                } else {
+                    Some(Step::new(
+                        false,
+                        if blink == code - 1 { PAUSE } else { GAP },
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// The pattern for a [`Status`]. See [`Status::blinks`].
+#[derive(Clone, Debug)]
+pub struct Blinks {
+    status: Status,
+    index: usize,
+}
+
+impl Iterator for Blinks {
+    type Item = Step<bool>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let step = self.status.step(self.index)?;
+        self.index += 1;
+        Some(step)
+    }
+}
+
+/// The signal for reporting the [`Status`] to a [`StatusLed`].
+pub type StatusSignal = Signal<CriticalSectionRawMutex, Status>;
+
+/// Reports the [`Status`] to a [`StatusLed`].
+#[derive(Clone, Copy)]
+pub struct StatusHandle<'a> {
+    signal: &'a StatusSignal,
+}
+
+impl StatusHandle<'_> {
+    /// Shows `status`.
+    pub fn set(&self, status: Status) {
+        self.signal.signal(status);
+    }
+
+    /// Switches the LED off.
+    pub fn off(&self) {
+        self.set(Status::Off);
+    }
+
+    /// Shows that everything is fine.
+    pub fn heartbeat(&self) {
+        self.set(Status::Heartbeat);
+    }
+
+    /// Shows that the firmware is working on something.
+    pub fn busy(&self) {
+        self.set(Status::Busy);
+    }
+
+    /// Shows that the firmware waits for the user.
+    pub fn attention(&self) {
+        self.set(Status::Attention);
+    }
+
+    /// Shows the error `code`.
+    pub fn error(&self, code: u8) {
+        self.set(Status::Error(code));
+    }
+}
+
+/// Blinks an LED in the pattern of the [`Status`] reported through [`StatusHandle`]s.
+pub struct StatusLed<'a, L> {
+    led: L,
+    signal: &'a StatusSignal,
+    status: Status,
+}
+
+impl<'a, L: Switch> StatusLed<'a, L> {
+    /// Creates a status LED receiving its status through `signal`. It starts with [`Status::Off`].
+    pub fn new(led: L, signal: &'a StatusSignal) -> Self {
+        Self {
+            led,
+            signal,
+            status: Status::Off,
+        }
+    }
+
+    /// Returns a handle for reporting the status.
+    pub fn handle(&self) -> StatusHandle<'a> {
+        StatusHandle {
+            signal: self.signal,
+        }
+    }
+
+    /// Runs the status LED, showing the latest status forever.
+    pub async fn run(mut self) -> ! {
+        let mut blinks = self.status.blinks();
+        let mut start = Instant::now();
+        // Whether to keep the LED dark for a moment before starting the pattern of a new status.
+        let mut gap = false;
+
+        loop {
+            let step = if core::mem::take(&mut gap) {
+                Step::new(false, GAP)
+            } else if let Some(step) = blinks.next() {
+                step
+            } else {
+                blinks = self.status.blinks();
+                continue;
+            };
+            self.led.switch(step.frame);
+            // Start the next step right when this one ends. This keeps the rhythm from drifting.
+            let end = start + step.duration;
+
+            loop {
+                match select(Timer::at(end), self.signal.wait()).await {
+                    Either::First(()) => {
+                        start = end;
+                        break;
+                    }
+                    Either::Second(status) if status == self.status => {}
+                    Either::Second(status) => {
+                        // Without a gap, the new pattern might run into a blink of the previous
+                        // one. There is nothing to run into if the LED was off.
+                        gap = self.status != Status::Off;
+                        self.status = status;
+                        blinks = status.blinks();
+                        start = Instant::now();
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Runs a [`StatusLed`] for the LED on the ESP32-C3 board.
+#[cfg(feature = "esp32c3")]
+#[embassy_executor::task]
+pub async fn status_task(status_led: StatusLed<'static, crate::board::Led<'static>>) -> ! {
+    status_led.run().await
+}
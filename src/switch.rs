@@ -1,39 +1,332 @@
 //! GPIO pin abstractions for switching things on an off whithout having to remember the actual
 //! hardware setup behing it.
+//!
+//! There is the crate switch-hal for that, but it does not support embedded-hal 1.0 yet. So here
+//! comes a minimal replacement: the trait [`Switch`] and the implementations [`LowActiveSwitch`]
+//! and [`HighActiveSwitch`] for any embedded-hal 1.0 [`OutputPin`] which can't fail to switch.
+//! This is the case for the GPIOs of the ESP32-C3 like [`esp_hal::gpio::Output`].
 
-use esp_hal::gpio::Output;
+use core::convert::Infallible;
+use embedded_hal::digital::OutputPin;
+
+use embassy_time::Timer;
+
+use crate::diag::{classify, LedStatus, ProbePin, Pull, Readings, SETTLING_TIME};
+use crate::frame::Frame;
+use crate::gamma::{GammaTable, Level, GAMMA};
+
+/// Something which can be switched on and off, like an LED or a relay.
+pub trait Switch {
+    /// Turns the output on.
+    fn switch_on(&mut self);
+
+    /// Turns the output off.
+    fn switch_off(&mut self);
+
+    /// Returns whether the output is currently switched on.
+    fn is_on(&self) -> bool;
+
+    /// Takes note that the output has been switched to `on` by other means, like a [`Port`].
+    /// Switches keeping track of their state just update it without writing the output again. By
+    /// default, the output gets switched.
+    fn follow(&mut self, on: bool) {
+        self.switch(on);
+    }
+
+    /// Sets the output to the supplied state.
+    fn switch(&mut self, on: bool) {
+        match on {
+            true => self.switch_on(),
+            false => self.switch_off(),
+        }
+    }
+
+    /// Turns the output on if it is off and vice versa.
+    fn toggle(&mut self) {
+        self.switch(!self.is_on());
+    }
+
+    /// Captures the current state of the output for restoring it later on with
+    /// [`Switch::restore`].
+    fn snapshot(&self) -> Snapshot {
+        Snapshot { on: self.is_on() }
+    }
+
+    /// Sets the output back to the state captured with [`Switch::snapshot`].
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.switch(snapshot.on);
+    }
+}
+
+/// Something which can be dimmed in addition to being switched on and off, like an LED driven by
+/// PWM.
+///
+/// Switching it on sets the full brightness and switching it off a brightness of zero.
+pub trait Dimmable: Switch {
+    /// Sets the brightness as duty cycle from 0 (off) to 255 (fully on).
+    ///
+    /// The duty cycle is used as it is, without gamma correction. This is for callers which
+    /// already computed duty cycles, like the ones limiting them to a power budget. Use
+    /// [`Dimmable::set_level`] for perceptually even brightness steps.
+    fn set_brightness(&mut self, brightness: u8);
+
+    /// Returns the current brightness as duty cycle from 0 (off) to 255 (fully on).
+    fn brightness(&self) -> u8;
+
+    /// Returns the gamma table used by [`Dimmable::set_level`]. This is [`GAMMA`] unless the
+    /// implementation allows configuring a different one.
+    fn gamma(&self) -> &GammaTable {
+        &GAMMA
+    }
+
+    /// Sets the perceptual brightness level, either from 0 to 255 or from 0.0 to 1.0. The level
+    /// gets mapped to a duty cycle with the gamma table from [`Dimmable::gamma`].
+    fn set_level(&mut self, level: impl Into<Level>) {
+        let duty = self.gamma().duty(level.into());
+        self.set_brightness(duty);
+    }
+
+    /// Sets the perceptual brightness level and maps it to a duty cycle with the given gamma
+    /// table.
+    fn set_level_with(&mut self, level: impl Into<Level>, gamma: &GammaTable) {
+        self.set_brightness(gamma.duty(level.into()));
+    }
+}
+
+/// The state of a [`Switch`] as captured by [`Switch::snapshot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    on: bool,
+}
+
+impl Snapshot {
+    /// Returns whether the output was switched on when taking this snapshot.
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+}
 
 /// Convenience wrapper for switching outputs (like LEDs) without having to remember the actual
 /// hardware behind this task.
 ///
-/// There is the crate switch-hal for that, but it does not support embedded-hal
-/// 1.0 yet.
+/// The output is switched on by driving it low. This is how the LEDs on the Hakkaa board are
+/// connected.
 #[derive(Debug)]
-pub struct LowActiveSwitch<'a> {
-    inner: Output<'a>,
+pub struct LowActiveSwitch<P> {
+    inner: P,
+    on: bool,
 }
 
-impl<'a> LowActiveSwitch<'a> {
-    /// Creates a new `LowActiveSwitch` from the given output.
-    pub fn new(output: Output<'a>) -> Self {
-        Self { inner: output }
+impl<P: OutputPin<Error = Infallible>> LowActiveSwitch<P> {
+    /// Creates a new `LowActiveSwitch` from the given output and switches it off.
+    pub fn new(output: P) -> Self {
+        let mut switch = Self {
+            inner: output,
+            on: false,
+        };
+        switch.switch_off();
+        switch
     }
 
-    /// Turns the output on.
-    pub fn switch_off(&mut self) {
-        self.inner.set_high();
+    /// Returns the output this switch has been created from.
+    pub fn free(self) -> P {
+        self.inner
     }
+}
 
-    /// Turns the output off.
-    pub fn switch_on(&mut self) {
-        self.inner.set_low();
+impl<P: OutputPin<Error = Infallible> + ProbePin> LowActiveSwitch<P> {
+    /// Checks whether the LED connected to this output is present or whether it is missing or
+    /// badly soldered. See [`crate::diag`] for details.
+    ///
+    /// The output is released for taking the readings and set back to its previous state
+    /// afterwards. Finding bridges to other LEDs needs all of them. Use
+    /// [`crate::led::Storeys::diagnose`] for this.
+    pub async fn diagnose(&mut self) -> LedStatus {
+        classify(self.take_readings().await)
     }
 
-    /// Sets the output to the supplied state.
-    pub fn switch(&mut self, on: bool) {
-        match on {
-            true => self.switch_on(),
-            false => self.switch_off(),
+    pub(crate) async fn take_readings(&mut self) -> Readings {
+        let snapshot = self.snapshot();
+        self.switch_off();
+
+        self.set_pull(Some(Pull::Up));
+        Timer::after(SETTLING_TIME).await;
+        let pulled_up = self.is_pin_high();
+
+        self.set_pull(Some(Pull::Down));
+        Timer::after(SETTLING_TIME).await;
+        let pulled_down = self.is_pin_high();
+
+        self.set_pull(None);
+        self.restore(snapshot);
+
+        Readings {
+            pulled_up,
+            pulled_down,
+            bridged: false,
+        }
+    }
+
+    pub(crate) fn set_pull(&mut self, pull: Option<Pull>) {
+        self.inner.set_pull(pull);
+    }
+
+    pub(crate) fn is_pin_high(&mut self) -> bool {
+        self.inner.is_high()
+    }
+}
+
+impl<P: OutputPin<Error = Infallible>> Switch for LowActiveSwitch<P> {
+    fn switch_on(&mut self) {
+        let Ok(()) = self.inner.set_low();
+        self.on = true;
+    }
+
+    fn switch_off(&mut self) {
+        let Ok(()) = self.inner.set_high();
+        self.on = false;
+    }
+
+    fn is_on(&self) -> bool {
+        self.on
+    }
+
+    fn follow(&mut self, on: bool) {
+        self.on = on;
+    }
+}
+
+/// Convenience wrapper for switching outputs (like add-on LEDs or relays) without having to
+/// remember the actual hardware behind this task.
+///
+/// The output is switched on by driving it high.
+#[derive(Debug)]
+pub struct HighActiveSwitch<P> {
+    inner: P,
+    on: bool,
+}
+
+impl<P: OutputPin<Error = Infallible>> HighActiveSwitch<P> {
+    /// Creates a new `HighActiveSwitch` from the given output and switches it off.
+    pub fn new(output: P) -> Self {
+        let mut switch = Self {
+            inner: output,
+            on: false,
+        };
+        switch.switch_off();
+        switch
+    }
+
+    /// Returns the output this switch has been created from.
+    pub fn free(self) -> P {
+        self.inner
+    }
+}
+
+impl<P: OutputPin<Error = Infallible>> Switch for HighActiveSwitch<P> {
+    fn switch_on(&mut self) {
+        let Ok(()) = self.inner.set_high();
+        self.on = true;
+    }
+
+    fn switch_off(&mut self) {
+        let Ok(()) = self.inner.set_low();
+        self.on = false;
+    }
+
+    fn is_on(&self) -> bool {
+        self.on
+    }
+
+    fn follow(&mut self, on: bool) {
+        self.on = on;
+    }
+}
+
+/// A switch which isn't connected to anything.
+///
+/// This stands in for an output where a driver expects one which is used elsewhere, like the LED
+/// on the ESP32-C3 board when it is driven by a [`crate::status::StatusLed`] instead of an
+/// [`crate::service::LedService`]. It only remembers whether it is switched on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Unconnected {
+    on: bool,
+}
+
+impl Unconnected {
+    /// Creates a new `Unconnected` switch which is switched off.
+    pub const fn new() -> Self {
+        Self { on: false }
+    }
+}
+
+impl Switch for Unconnected {
+    fn switch_on(&mut self) {
+        self.on = true;
+    }
+
+    fn switch_off(&mut self) {
+        self.on = false;
+    }
+
+    fn is_on(&self) -> bool {
+        self.on
+    }
+}
+
+/// Writes the levels of `N` outputs of the same GPIO port at once.
+///
+/// Switching outputs one after another makes them change microseconds apart. A port writes all of
+/// them with back-to-back writes to the set and clear registers of the GPIO port instead. Outputs
+/// switching in the same direction change in the very same clock cycle.
+///
+/// The port does not own the outputs. It is meant for speeding up switching them along with their
+/// [`Switch`]es, see [`crate::led::Storeys::with_port`]. It can't be copied, so there is only one
+/// port writing to a set of outputs.
+#[derive(Debug)]
+pub struct Port<const N: usize> {
+    masks: [u32; N],
+    low_active: bool,
+    write: fn(set: u32, clear: u32),
+}
+
+impl<const N: usize> Port<N> {
+    /// Creates a port for outputs switched on by driving them low, like the LEDs on the Hakkaa
+    /// board. The element at index _n_ of `masks` is the bit of output _n_ in the port registers.
+    /// The function `write` sets the bits of `set` and clears the bits of `clear` in the output
+    /// register.
+    pub const fn low_active(masks: [u32; N], write: fn(set: u32, clear: u32)) -> Self {
+        Self {
+            masks,
+            low_active: true,
+            write,
+        }
+    }
+
+    /// Creates a port for outputs switched on by driving them high. See [`Port::low_active`] for
+    /// the arguments.
+    pub const fn high_active(masks: [u32; N], write: fn(set: u32, clear: u32)) -> Self {
+        Self {
+            masks,
+            low_active: false,
+            write,
+        }
+    }
+
+    /// Switches output _n_ on or off as given by the LED at index _n_ of `frame`.
+    pub fn write(&self, frame: Frame<N>) {
+        let (on, off) =
+            self.masks
+                .iter()
+                .zip(frame.iter())
+                .fold((0, 0), |(on, off), (mask, lit)| match lit {
+                    true => (on | mask, off),
+                    false => (on, off | mask),
+                });
+
+        match self.low_active {
+            true => (self.write)(off, on),
+            false => (self.write)(on, off),
         }
     }
 }
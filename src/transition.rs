@@ -0,0 +1,185 @@
+//! Transitions between frames instead of hard cuts.
+//!
+//! A [`Transition`] turns the change from one frame to another into an [`Animation`] of brightness
+//! levels for dimmable LEDs. Its progress is shaped by an [`Easing`] curve. The storey LEDs
+//! transition to a new frame with [`crate::led::Storeys::transition_to`]:
+//!
+//! ```rust,ignore
+//! let transition = Transition::wipe(Duration::from_millis(400), Direction::Up)
+//!     .with_easing(Easing::EaseInOut);
+//! storeys.transition_to(Frame::from_art("#.#.#.#."), transition).await;
+//! ```
+//!
+//! Frames convert to brightness levels with all LEDs switched on at full brightness. Transitions
+//! work between dimmed levels as well.
+
+use embassy_time::Duration;
+
+use crate::animation::{Animation, Step};
+use crate::easing::Easing;
+use crate::frame::Direction;
+use crate::gamma::Level;
+use crate::random::{xorshift, DEFAULT_SEED};
+
+/// The kinds of [`Transition`]s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// All LEDs fade from their old to their new level at the same time.
+    Crossfade,
+    /// The new levels sweep over the old ones in the given direction.
+    Wipe(Direction),
+    /// The LEDs switch from their old to their new level one at a time in random order. The
+    /// order is determined by the seed.
+    Dissolve(u32),
+    /// The old levels move out and the new levels move in in the given direction.
+    Slide(Direction),
+}
+
+/// A change from one frame to another taking some time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transition {
+    kind: Kind,
+    duration: Duration,
+    easing: Easing,
+    step: Duration,
+}
+
+impl Transition {
+    /// The default time between brightness updates. This looks smooth to the eye.
+    pub const DEFAULT_STEP: Duration = Duration::from_millis(20);
+
+    /// Creates a transition of the given kind taking `duration`.
+    pub fn new(kind: Kind, duration: Duration) -> Self {
+        Self {
+            kind,
+            duration,
+            easing: Easing::Linear,
+            step: Self::DEFAULT_STEP,
+        }
+    }
+
+    /// Creates a crossfade taking `duration`.
+    pub fn crossfade(duration: Duration) -> Self {
+        Self::new(Kind::Crossfade, duration)
+    }
+
+    /// Creates a wipe in `direction` taking `duration`.
+    pub fn wipe(duration: Duration, direction: Direction) -> Self {
+        Self::new(Kind::Wipe(direction), duration)
+    }
+
+    /// Creates a dissolve taking `duration` with the seed [`DEFAULT_SEED`].
+    pub fn dissolve(duration: Duration) -> Self {
+        Self::new(Kind::Dissolve(DEFAULT_SEED), duration)
+    }
+
+    /// Creates a slide in `direction` taking `duration`.
+    pub fn slide(duration: Duration, direction: Direction) -> Self {
+        Self::new(Kind::Slide(direction), duration)
+    }
+
+    /// Sets the easing curve for the progress of the transition. The default is
+    /// [`Easing::Linear`].
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Sets the time between brightness updates.
+    pub fn with_step(mut self, step: Duration) -> Self {
+        self.step = step;
+        self
+    }
+
+    /// Returns the levels shown at the linear `progress` from 0.0 to 1.0 of the transition from
+    /// `from` to `to`.
+    pub fn levels<const N: usize>(
+        &self,
+        from: &[Level; N],
+        to: &[Level; N],
+        progress: f32,
+    ) -> [Level; N] {
+        let progress = self.easing.apply(progress);
+
+        match self.kind {
+            Kind::Crossfade => core::array::from_fn(|i| mix(from[i], to[i], progress)),
+            Kind::Wipe(direction) => {
+                let edge = progress * N as f32;
+                core::array::from_fn(|i| {
+                    let position = direction.index(i, N) as f32;
+                    mix(from[i], to[i], (edge - position).clamp(0.0, 1.0))
+                })
+            }
+            Kind::Dissolve(seed) => {
+                let ranks = ranks::<N>(seed);
+                core::array::from_fn(|i| {
+                    let threshold = (ranks[i] + 1) as f32 / (N + 1) as f32;
+                    if progress >= threshold {
+                        to[i]
+                    } else {
+                        from[i]
+                    }
+                })
+            }
+            Kind::Slide(direction) => {
+                // Both frames form a strip with the new levels in front of the old ones. The strip
+                // moves over the LEDs by up to its half.
+                let strip = |position: usize| {
+                    if position < N {
+                        to[direction.index(position, N)]
+                    } else {
+                        from[direction.index(position - N, N)]
+                    }
+                };
+                let offset = N as f32 * (1.0 - progress);
+                let levels: [Level; N] = core::array::from_fn(|i| {
+                    let position = offset + i as f32;
+                    let index = position as usize;
+                    let next = (index + 1).min(2 * N - 1);
+                    mix(strip(index), strip(next), position - index as f32)
+                });
+                core::array::from_fn(|i| levels[direction.index(i, N)])
+            }
+        }
+    }
+
+    /// Returns the transition from `from` to `to` as animation. The last step shows `to`.
+    pub fn animation<const N: usize>(
+        self,
+        from: impl Into<[Level; N]>,
+        to: impl Into<[Level; N]>,
+    ) -> impl Animation<[Level; N]> + Clone + DoubleEndedIterator + ExactSizeIterator {
+        let (from, to) = (from.into(), to.into());
+        let duration = self.duration.as_ticks();
+        let steps = (duration / self.step.as_ticks().max(1)).max(1);
+
+        (0..steps as usize).map(move |step| {
+            let step = step as u64 + 1;
+            let levels = self.levels(&from, &to, step as f32 / steps as f32);
+
+            // Spread rounding errors so that the steps add up to the duration exactly.
+            let duration = step * duration / steps - (step - 1) * duration / steps;
+            Step::new(levels, Duration::from_ticks(duration))
+        })
+    }
+}
+
+/// Mixes `to` into `from` by `amount` from 0.0 to 1.0.
+fn mix(from: Level, to: Level, amount: f32) -> Level {
+    let from = f32::from(from.0);
+    let to = f32::from(to.0);
+    Level::from((from + (to - from) * amount) / f32::from(u8::MAX))
+}
+
+/// Returns a random rank from 0 to `N - 1` for each LED.
+fn ranks<const N: usize>(seed: u32) -> [usize; N] {
+    let mut ranks: [usize; N] = core::array::from_fn(|i| i);
+    let mut state = seed.max(1);
+
+    // Shuffle with Fisher-Yates.
+    for i in (1..N).rev() {
+        state = xorshift(state);
+        ranks.swap(i, state as usize % (i + 1));
+    }
+    ranks
+}
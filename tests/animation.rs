@@ -0,0 +1,174 @@
+//! Host tests for composing animations.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use embassy_time::Duration;
+use hakkaa::animation::{self, Animation, Keyframes, LoopMode, Step};
+use hakkaa::frame::Frame;
+
+const STEP: Duration = Duration::from_millis(100);
+
+/// Renders the frames of an animation as ASCII art, one frame per step.
+fn art<const N: usize>(animation: impl Animation<Frame<N>>) -> Vec<String> {
+    animation.map(|step| step.frame.to_string()).collect()
+}
+
+#[test]
+fn cycle() {
+    assert_eq!(
+        art(animation::cycle::<4>(STEP)),
+        ["#...", ".#..", "..#.", "...#"]
+    );
+}
+
+#[test]
+fn then_and_repeat() {
+    let animation = animation::blink::<4>(STEP)
+        .repeat(2)
+        .then(animation::cycle(STEP).take(1));
+
+    assert_eq!(art(animation), ["####", "....", "####", "....", "#..."]);
+}
+
+#[test]
+fn reverse() {
+    assert_eq!(
+        art(animation::cycle::<3>(STEP).reverse()),
+        ["..#", ".#.", "#.."]
+    );
+}
+
+#[test]
+fn ping_pong() {
+    let animation = animation::cycle::<4>(STEP).ping_pong();
+
+    assert_eq!(
+        art(animation.repeat(2)),
+        [
+            "#...", ".#..", "..#.", "...#", "..#.", ".#..", "#...", ".#..", "..#.", "...#", "..#.",
+            ".#.."
+        ]
+    );
+    assert_eq!(art(animation::cycle::<1>(STEP).ping_pong()), ["#"]);
+    assert_eq!(art(animation::cycle::<2>(STEP).ping_pong()), ["#.", ".#"]);
+}
+
+#[test]
+fn limit() {
+    let animation = animation::cycle::<4>(STEP)
+        .repeat_forever()
+        .limit(Duration::from_millis(250));
+    let steps: Vec<Step<Frame<4>>> = animation.collect();
+
+    assert_eq!(steps.len(), 3);
+    assert_eq!(steps[2].frame, Frame::single(2));
+    assert_eq!(steps[2].duration, Duration::from_millis(50));
+}
+
+#[test]
+fn speed() {
+    let animation = animation::blink::<8>(STEP);
+
+    assert_eq!(
+        animation.clone().speed(4.0).total_duration(),
+        Duration::from_millis(50)
+    );
+    assert_eq!(
+        animation.speed(0.5).total_duration(),
+        Duration::from_millis(400)
+    );
+}
+
+#[test]
+fn speed_keeps_steps_from_vanishing() {
+    let durations: Vec<Duration> = animation::blink::<8>(STEP)
+        .speed(1e30)
+        .map(|step| step.duration)
+        .collect();
+
+    assert_eq!(durations, [Duration::from_ticks(1); 2]);
+}
+
+#[test]
+#[should_panic(expected = "positive and finite")]
+fn speed_zero() {
+    animation::blink::<8>(STEP).speed(0.0);
+}
+
+#[test]
+#[should_panic(expected = "positive and finite")]
+fn speed_nan() {
+    animation::blink::<8>(STEP).speed(f32::NAN);
+}
+
+#[test]
+fn frames() {
+    let frames = [Frame::D1 | Frame::D8, Frame::D4 | Frame::D5];
+
+    assert_eq!(
+        art(animation::frames(&frames, STEP).reverse()),
+        ["...##...", "#......#"]
+    );
+}
+
+static THREE: [Step<Frame<4>>; 3] = [
+    Step::new(Frame::from_art("#..."), STEP),
+    Step::new(Frame::from_art(".#.."), Duration::from_millis(200)),
+    Step::new(Frame::from_art("..#."), Duration::from_millis(300)),
+];
+
+#[test]
+fn keyframes_once_and_times() {
+    let once = Keyframes::new(&THREE);
+    assert_eq!(art(once.steps()), ["#...", ".#..", "..#."]);
+    assert_eq!(once.steps().total_duration(), Duration::from_millis(600));
+
+    let twice = once.with_loop(LoopMode::Times(2));
+    assert_eq!(
+        art(twice.steps()),
+        ["#...", ".#..", "..#.", "#...", ".#..", "..#."]
+    );
+    assert_eq!(once.with_loop(LoopMode::Times(0)).steps().count(), 0);
+}
+
+#[test]
+fn keyframes_forever_and_ping_pong() {
+    let forever = Keyframes::new(&THREE).with_loop(LoopMode::Forever);
+    assert_eq!(
+        art(forever.steps().take(5)),
+        ["#...", ".#..", "..#.", "#...", ".#.."]
+    );
+
+    let ping_pong = Keyframes::new(&THREE).with_loop(LoopMode::PingPong);
+    assert_eq!(
+        art(ping_pong.steps().take(7)),
+        ["#...", ".#..", "..#.", ".#..", "#...", ".#..", "..#."]
+    );
+    let durations: Vec<u64> = ping_pong
+        .steps()
+        .take(4)
+        .map(|step| step.duration.as_millis())
+        .collect();
+    assert_eq!(durations, [100, 200, 300, 200]);
+}
+
+#[test]
+fn keyframes_degenerate() {
+    static ONE: [Step<Frame<4>>; 1] = [Step::new(Frame::from_art("#..."), STEP)];
+
+    let empty: Keyframes<Frame<4>> = Keyframes::new(&[]).with_loop(LoopMode::Forever);
+    assert_eq!(empty.steps().next(), None);
+    let one = Keyframes::new(&ONE).with_loop(LoopMode::PingPong);
+    assert_eq!(art(one.steps().take(3)), ["#...", "#...", "#..."]);
+
+    // Repeating frames without any duration would never end.
+    static TIMELESS: [Step<Frame<4>>; 2] = [
+        Step::new(Frame::from_art("#..."), Duration::MIN),
+        Step::new(Frame::from_art(".#.."), Duration::MIN),
+    ];
+    let timeless = Keyframes::new(&TIMELESS);
+    assert_eq!(timeless.with_loop(LoopMode::Forever).steps().count(), 2);
+    assert_eq!(timeless.with_loop(LoopMode::PingPong).steps().count(), 2);
+    assert_eq!(timeless.with_loop(LoopMode::Times(1000)).steps().count(), 2);
+    assert_eq!(timeless.with_loop(LoopMode::Times(0)).steps().count(), 0);
+}
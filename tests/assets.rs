@@ -0,0 +1,233 @@
+//! Host tests for converting the images in `assets/` into patterns at build time.
+//!
+//! See "Tests on the host" in the README for running them.
+
+#[path = "../build/assets.rs"]
+mod assets;
+
+use std::path::Path;
+
+use assets::{Asset, Conversion, Format, Image};
+use hakkaa::frame::Frame;
+use hakkaa::pattern;
+
+/// An arrow pointing up, with D1 at the bottom.
+const ARROW: &str = "P1
+# An arrow
+5 8
+0 0 1 0 0
+0 1 1 1 0
+1 0 1 0 1
+0 0 1 0 0
+0 0 1 0 0
+0 0 1 0 0
+0 0 1 0 0
+0 0 1 0 0
+";
+
+fn decode_pbm(data: &[u8]) -> Image {
+    assets::decode(Format::Pbm, data).unwrap()
+}
+
+/// Encodes a PNG image with `samples` per pixel.
+fn png(width: u32, height: u32, color: png::ColorType, samples: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width, height);
+    encoder.set_color(color);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(samples)
+        .unwrap();
+    data
+}
+
+/// Renders columns as ASCII art frames.
+fn art(columns: &[u8]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|column| Frame::<8>::from_bits(u64::from(*column)).to_string())
+        .collect()
+}
+
+#[test]
+fn plain_pbm() {
+    let image = decode_pbm(ARROW.as_bytes());
+
+    assert_eq!((image.width, image.height), (5, 8));
+    assert_eq!(
+        art(&assets::convert(&image, Conversion::Threshold)),
+        [".....#..", "......#.", "########", "......#.", ".....#..",]
+    );
+}
+
+#[test]
+fn raw_pbm() {
+    // The same arrow with rows padded to whole bytes.
+    let mut raw = b"P4 5 8\n".to_vec();
+    raw.extend([0x20, 0x70, 0xa8, 0x20, 0x20, 0x20, 0x20, 0x20]);
+
+    assert_eq!(decode_pbm(&raw), decode_pbm(ARROW.as_bytes()));
+    assert!(assets::decode(Format::Pbm, &raw[..raw.len() - 1]).is_err());
+}
+
+#[test]
+fn invalid_pbm() {
+    let decode = |data: &str| assets::decode(Format::Pbm, data.as_bytes());
+
+    assert!(decode("P2 1 1 0").is_err());
+    assert!(decode("P1 2 1 1").is_err());
+    assert!(decode("P1 1 1 2").is_err());
+    assert!(decode("P1 0 8").is_err());
+    assert!(decode("P1 x 8").is_err());
+}
+
+#[test]
+fn png_colors_and_transparency() {
+    let gray = png(2, 1, png::ColorType::Grayscale, &[0, 255]);
+    let rgba = png(
+        3,
+        1,
+        png::ColorType::Rgba,
+        &[0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 0],
+    );
+
+    let gray = assets::decode(Format::Png, &gray).unwrap();
+    let rgba = assets::decode(Format::Png, &rgba).unwrap();
+
+    // Black is full ink, white and transparent pixels have none.
+    assert_eq!(gray.ink, [255, 0]);
+    assert_eq!(rgba.ink, [255, 0, 0]);
+    assert!(assets::decode(Format::Png, b"not a PNG").is_err());
+}
+
+#[test]
+fn scale_to_eight_rows() {
+    // A 4 by 16 image with its left half black. It becomes 2 by 8.
+    let samples: Vec<u8> = (0..16).flat_map(|_| [0, 0, 255, 255]).collect();
+    let image = assets::decode(
+        Format::Png,
+        &png(4, 16, png::ColorType::Grayscale, &samples),
+    )
+    .unwrap();
+
+    assert_eq!(
+        art(&assets::convert(&image, Conversion::Threshold)),
+        ["########", "........"]
+    );
+}
+
+#[test]
+fn threshold_and_dither() {
+    // A quarter of the ink everywhere.
+    let image = Image {
+        width: 16,
+        height: 8,
+        ink: vec![64; 16 * 8],
+    };
+
+    let threshold = assets::convert(&image, Conversion::Threshold);
+    let dither = assets::convert(&image, Conversion::Dither);
+
+    assert!(threshold.iter().all(|column| *column == 0));
+    let on: u32 = dither.iter().map(|column| column.count_ones()).sum();
+    assert!((28..=36).contains(&on), "{on}");
+}
+
+#[test]
+fn asset_names_and_options() {
+    let asset = |name: &str| Asset::from_file_name(name);
+
+    assert_eq!(
+        asset("big-smile.PNG"),
+        Ok(Some(Asset {
+            name: "BIG_SMILE".into(),
+            format: Format::Png,
+            conversion: Conversion::Threshold,
+        }))
+    );
+    assert_eq!(
+        asset("photo.dither.pbm").unwrap().unwrap().conversion,
+        Conversion::Dither
+    );
+    assert_eq!(asset("README.md"), Ok(None));
+    assert!(asset("1st.png").is_err());
+    assert!(asset("photo.blur.png").is_err());
+}
+
+#[test]
+fn generated_code() {
+    assert_eq!(
+        assets::generate("DOTS", "assets/dots.pbm", &[0b1, 0b1000_0000]),
+        "/// Generated from `assets/dots.pbm`.
+pub static DOTS: [crate::frame::Frame; 2] = [
+    crate::frame::Frame::from_bits(0b00000001),
+    crate::frame::Frame::from_bits(0b10000000),
+];
+"
+    );
+}
+
+#[test]
+fn convert_directory() {
+    let dir = std::env::temp_dir().join(format!("hakkaa-assets-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("b.pbm"), "P1 1 8 1 0 0 0 0 0 0 0").unwrap();
+    std::fs::write(dir.join("a.pbm"), "P1 1 8 0 0 0 0 0 0 0 1").unwrap();
+    std::fs::write(dir.join("notes.txt"), "not an image").unwrap();
+
+    let code = assets::convert_dir(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let code = code.unwrap();
+    assert!(code.find("pub static A:").unwrap() < code.find("pub static B:").unwrap());
+    assert!(code.contains("from_bits(0b00000001),\n];\n/// Generated"));
+    assert!(code.ends_with("from_bits(0b10000000),\n];\n"));
+    assert_eq!(
+        assets::convert_dir(Path::new("no such directory")),
+        Ok(String::new())
+    );
+}
+
+#[test]
+fn name_collisions() {
+    let convert = |files: [&str; 2]| {
+        let dir =
+            std::env::temp_dir().join(format!("hakkaa-assets-{}-{}", std::process::id(), files[1]));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            std::fs::write(dir.join(file), "P1 1 8 1 0 0 0 0 0 0 0").unwrap();
+        }
+        let code = assets::convert_dir(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        code.map_err(|error| error.replace(&dir.display().to_string(), "assets"))
+    };
+
+    assert_eq!(
+        convert(["smile.pbm", "smile.png"]),
+        Err("assets/smile.pbm and assets/smile.png both become the pattern SMILE".into())
+    );
+    assert_eq!(
+        convert(["smile-face.pbm", "smile_face.dither.pbm"]),
+        Err(
+            "assets/smile-face.pbm and assets/smile_face.dither.pbm both become the pattern \
+             SMILE_FACE"
+                .into()
+        )
+    );
+}
+
+#[test]
+fn smile_asset() {
+    #[rustfmt::skip]
+    let smile: [Frame; 26] = pattern![
+        "........", "........", "........", "........",
+        "......##", "......##", "....#...", "...#....", "..#.....",
+        ".#......", ".#......", "#.......", "#.......", "#.......", "#.......",
+        ".#......", ".#......", "..#.....", "...#....", "....#...",
+        "......##", "......##", "........", "........", "........", "........",
+    ];
+
+    assert_eq!(hakkaa::assets::SMILE, smile);
+}
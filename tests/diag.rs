@@ -0,0 +1,49 @@
+//! Host tests for classifying LEDs from recorded pin readings.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use hakkaa::diag::{classify, LedStatus, Readings};
+
+#[test]
+fn present() {
+    let readings = Readings {
+        pulled_up: true,
+        pulled_down: true,
+        bridged: false,
+    };
+
+    assert_eq!(classify(readings), LedStatus::Present);
+}
+
+#[test]
+fn open() {
+    let readings = Readings {
+        pulled_up: true,
+        pulled_down: false,
+        bridged: false,
+    };
+
+    assert_eq!(classify(readings), LedStatus::Open);
+}
+
+#[test]
+fn shorted_to_ground() {
+    let readings = Readings {
+        pulled_up: false,
+        pulled_down: false,
+        bridged: false,
+    };
+
+    assert_eq!(classify(readings), LedStatus::Shorted);
+}
+
+#[test]
+fn bridged() {
+    let readings = Readings {
+        pulled_up: true,
+        pulled_down: true,
+        bridged: true,
+    };
+
+    assert_eq!(classify(readings), LedStatus::Shorted);
+}
@@ -0,0 +1,55 @@
+//! Host tests for the easing curves.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use hakkaa::easing::Easing;
+
+const ALL: [Easing; 8] = [
+    Easing::Linear,
+    Easing::EaseIn,
+    Easing::EaseOut,
+    Easing::EaseInOut,
+    Easing::CubicIn,
+    Easing::CubicOut,
+    Easing::CubicInOut,
+    Easing::Bounce,
+];
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+#[test]
+fn start_and_end() {
+    for easing in ALL {
+        assert!(close(easing.apply(0.0), 0.0), "{easing:?}");
+        assert!(close(easing.apply(1.0), 1.0), "{easing:?}");
+        assert!(close(easing.apply(-1.0), 0.0), "{easing:?}");
+        assert!(close(easing.apply(2.0), 1.0), "{easing:?}");
+    }
+}
+
+#[test]
+fn stay_in_range() {
+    for easing in ALL {
+        for step in 0..=100 {
+            let progress = easing.apply(step as f32 / 100.0);
+            assert!((0.0..=1.0 + 1e-4).contains(&progress), "{easing:?} {step}");
+        }
+    }
+}
+
+#[test]
+fn shapes() {
+    assert!(close(Easing::EaseIn.apply(0.5), 0.25));
+    assert!(close(Easing::EaseOut.apply(0.5), 0.75));
+    assert!(close(Easing::EaseInOut.apply(0.5), 0.5));
+    assert!(close(Easing::CubicIn.apply(0.5), 0.125));
+    assert!(close(Easing::CubicOut.apply(0.5), 0.875));
+    assert!(close(Easing::CubicInOut.apply(0.25), 0.0625));
+
+    // The ball hits the end at the borders of the bounces.
+    assert!(close(Easing::Bounce.apply(1.0 / 2.75), 1.0));
+    assert!(close(Easing::Bounce.apply(2.0 / 2.75), 1.0));
+    assert!(Easing::Bounce.apply(1.5 / 2.75) < 1.0);
+}
@@ -0,0 +1,170 @@
+//! Host tests previewing the built-in effects.
+//!
+//! See "Tests on the host" in the README for running them.
+//! Print the previews with `-- --nocapture`.
+
+use embassy_time::{Duration, Instant};
+use hakkaa::animation::Animation;
+use hakkaa::effects::{Bounce, Breathing, Fill, Scanner, Sparkle, TheaterChase};
+use hakkaa::frame::{Direction, Frame};
+use hakkaa::led::Storeys;
+use hakkaa::mock::{self, Simulation};
+
+const STEP: Duration = Duration::from_millis(100);
+
+fn preview<F: mock::Render>(animation: impl Animation<F>) -> Vec<String> {
+    let lines = mock::preview(animation);
+    for line in &lines {
+        println!("{line}");
+    }
+    lines
+}
+
+#[test]
+fn scanner() {
+    let lines = preview(Scanner::new(STEP).with_tail(2).animation::<5>());
+
+    assert_eq!(
+        lines,
+        [
+            "#+-.. 100 ms",
+            "+#... 100 ms",
+            "-+#.. 100 ms",
+            ".-+#. 100 ms",
+            "..-+# 100 ms",
+            "...#+ 100 ms",
+            "..#+- 100 ms",
+            ".#+-. 100 ms",
+        ]
+    );
+}
+
+#[test]
+fn scanner_down() {
+    let lines = preview(
+        Scanner::new(STEP)
+            .with_tail(0)
+            .with_direction(Direction::Down)
+            .animation::<3>(),
+    );
+
+    assert_eq!(
+        lines,
+        ["..# 100 ms", ".#. 100 ms", "#.. 100 ms", ".#. 100 ms"]
+    );
+}
+
+#[test]
+fn bounce() {
+    let animation = Bounce::new(Duration::from_millis(300)).animation::<4>();
+    let lines = preview(animation.clone());
+
+    assert_eq!(
+        lines.iter().map(|line| &line[..4]).collect::<Vec<_>>(),
+        ["...#", "..#.", ".#..", "#...", ".#..", "..#."]
+    );
+    assert_eq!(
+        animation.clone().total_duration(),
+        Duration::from_millis(600)
+    );
+
+    // Falling speeds up and rising slows down again.
+    let durations: Vec<Duration> = animation.map(|step| step.duration).collect();
+    assert!(durations[1] > durations[2]);
+    assert_eq!(durations[1], durations[5]);
+    assert_eq!(durations[2], durations[4]);
+}
+
+#[test]
+fn fill_and_drain() {
+    let lines = preview(
+        Fill::new(STEP)
+            .with_direction(Direction::Down)
+            .animation::<3>(),
+    );
+
+    assert_eq!(
+        lines,
+        [
+            "..# 100 ms",
+            ".## 100 ms",
+            "### 100 ms",
+            ".## 100 ms",
+            "..# 100 ms",
+            "... 100 ms",
+        ]
+    );
+}
+
+#[test]
+fn theater_chase() {
+    let lines = preview(TheaterChase::new(STEP).animation::<8>());
+
+    assert_eq!(
+        lines,
+        ["#..#..#. 100 ms", ".#..#..# 100 ms", "..#..#.. 100 ms"]
+    );
+}
+
+#[test]
+fn sparkle() {
+    let animation = Sparkle::new(STEP).with_density(0.25).animation::<8>();
+    let frames: Vec<Frame> = animation.clone().take(100).map(|step| step.frame).collect();
+    preview(animation.clone().take(5));
+
+    // The same seed results in the same sparkles and about a quarter of the LEDs is lit.
+    assert_eq!(
+        frames,
+        animation
+            .take(100)
+            .map(|step| step.frame)
+            .collect::<Vec<_>>()
+    );
+    let lit: u32 = frames.iter().map(|frame| frame.count_on()).sum();
+    assert!((150..250).contains(&lit), "{lit} LEDs lit");
+    assert!(frames.windows(2).any(|frames| frames[0] != frames[1]));
+}
+
+#[test]
+fn breathing() {
+    let animation = Breathing::new(Duration::from_secs(1))
+        .with_step(Duration::from_millis(125))
+        .animation::<2>();
+    let lines = preview(animation.clone());
+
+    assert_eq!(
+        lines,
+        [
+            ".. 125 ms",
+            "-- 125 ms",
+            "++ 125 ms",
+            "** 125 ms",
+            "## 125 ms",
+            "** 125 ms",
+            "++ 125 ms",
+            "-- 125 ms",
+        ]
+    );
+    assert_eq!(animation.total_duration(), Duration::from_secs(1));
+}
+
+#[test]
+fn play_levels() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<5>();
+    let mut storeys = Storeys::new(switches);
+    let scanner = Scanner::new(STEP).with_tail(1).animation();
+
+    simulation.run_for(
+        storeys.play_levels(scanner.repeat_forever()),
+        Duration::from_millis(850),
+    );
+
+    // The bottom LED lights up at the start of each sweep and fades out behind the dot.
+    let transitions = recorders[0].transitions();
+    assert_eq!(transitions[0].at, Instant::from_millis(0));
+    assert_eq!(transitions[0].brightness, 255);
+    assert!(transitions[1].brightness > 0 && transitions[1].brightness < 255);
+    assert_eq!(transitions.last().unwrap().at, Instant::from_millis(800));
+    assert_eq!(storeys.brightness()[0], 255);
+}
@@ -0,0 +1,115 @@
+//! Host tests for frames.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use hakkaa::frame::{BitOrder, Frame, ParseFrameError};
+use hakkaa::pattern;
+
+fn frame(art: &str) -> Frame {
+    art.parse().unwrap()
+}
+
+#[test]
+fn art_round_trip() {
+    let frame = frame("#..#..##");
+
+    assert_eq!(frame.bits(), 0b1100_1001);
+    assert_eq!(frame.to_string(), "#..#..##");
+    assert_eq!(format!("{frame:?}"), "Frame(\"#..#..##\")");
+    assert_eq!(Frame::<8>::from_bits(0b1100_1001), frame);
+}
+
+#[test]
+fn invalid_art() {
+    assert_eq!(
+        "#..#..#".parse::<Frame>(),
+        Err(ParseFrameError::Length {
+            expected: 8,
+            found: 7
+        })
+    );
+    assert_eq!(
+        "#..x..##".parse::<Frame>(),
+        Err(ParseFrameError::Character { index: 3 })
+    );
+    assert!("#".repeat(80).parse::<Frame>().is_err());
+}
+
+#[test]
+fn led_access() {
+    let mut frame = frame("#.......");
+
+    frame.set(3, true);
+    frame.set(0, false);
+
+    assert!(frame[3]);
+    assert!(!frame.get(0));
+    assert_eq!(frame, Frame::D4);
+    assert!(frame.contains(Frame::D4));
+    assert_eq!(frame.with(7, true), Frame::D4 | Frame::D8);
+    assert!(<[bool; 8]>::from(Frame::D2)[1]);
+}
+
+#[test]
+fn shift_and_rotate() {
+    let frame = frame("##.....#");
+
+    assert_eq!(frame.shift_left(1), self::frame(".##....."));
+    assert_eq!(frame.shift_right(1), self::frame("#.....#."));
+    assert_eq!(frame.shift_left(8), Frame::EMPTY);
+    assert_eq!(frame.rotate_left(1), self::frame("###....."));
+    assert_eq!(frame.rotate_right(2), self::frame(".....###"));
+    assert_eq!(frame.rotate_left(8), frame);
+}
+
+#[test]
+fn mirror_and_invert() {
+    let frame = frame("##.#....");
+
+    assert_eq!(frame.mirror(), self::frame("....#.##"));
+    assert_eq!(frame.invert(), self::frame("..#.####"));
+    assert_eq!(!frame, frame.invert());
+    assert_eq!(Frame::<8>::FULL.invert(), Frame::EMPTY);
+}
+
+#[test]
+fn boolean_combination() {
+    let a = frame("##..##..");
+    let b = frame("#.#.#.#.");
+
+    assert_eq!(a & b, frame("#...#..."));
+    assert_eq!(a | b, frame("###.###."));
+    assert_eq!(a ^ b, frame(".##..##."));
+}
+
+#[test]
+fn other_sizes() {
+    let frame: Frame<12> = "#..........#".parse().unwrap();
+
+    assert_eq!(frame.mirror(), frame);
+    assert_eq!(frame.rotate_left(1).to_string(), "##..........");
+    assert_eq!(Frame::<64>::FULL.count_on(), 64);
+    assert_eq!(Frame::<64>::FULL.mirror(), Frame::FULL);
+}
+
+#[test]
+fn pattern() {
+    static ROWS: [Frame; 3] = pattern!["#.......", "........", "......##",];
+    let small: [Frame<4>; 2] = pattern!["#..#", ".##."];
+
+    assert_eq!(ROWS, [Frame::D1, Frame::EMPTY, Frame::D7 | Frame::D8]);
+    assert_eq!(small, [Frame::from_bits(0b1001), Frame::from_bits(0b0110)]);
+}
+
+#[test]
+fn pattern_bit_order() {
+    let lsb_first: [Frame; 1] = pattern![LsbFirst; "##......"];
+    let msb_first: [Frame; 1] = pattern![MsbFirst; "##......"];
+
+    assert_eq!(lsb_first, [Frame::from_bits(0b0000_0011)]);
+    assert_eq!(msb_first, [Frame::from_bits(0b1100_0000)]);
+    assert_eq!(
+        Frame::<8>::from_art_with("#.#.....", BitOrder::MsbFirst).to_string(),
+        ".....#.#"
+    );
+}
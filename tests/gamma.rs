@@ -0,0 +1,75 @@
+//! Host tests for the gamma correction.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use hakkaa::gamma::{GammaTable, Level, DEFAULT_GAMMA, GAMMA};
+
+#[test]
+fn end_points() {
+    assert_eq!(GAMMA.duty(Level::OFF), 0);
+    assert_eq!(GAMMA.duty(Level::FULL), 255);
+}
+
+#[test]
+fn matches_powf() {
+    for level in 0..=255u8 {
+        let expected = (255.0 * (f64::from(level) / 255.0).powf(DEFAULT_GAMMA.into())).round();
+        let expected = if level > 0 {
+            expected.max(1.0)
+        } else {
+            expected
+        };
+        let duty = f64::from(GAMMA.duty(Level(level)));
+
+        assert!(
+            (duty - expected).abs() <= 1.0,
+            "level {level}: duty {duty}, expected {expected}"
+        );
+    }
+}
+
+#[test]
+fn monotonic_and_lit() {
+    for level in 1..=255u8 {
+        let duty = GAMMA.duty(Level(level));
+
+        assert!(duty > 0);
+        assert!(duty >= GAMMA.duty(Level(level - 1)));
+    }
+}
+
+#[test]
+fn linear_for_gamma_one() {
+    const LINEAR: GammaTable = GammaTable::new(1.0);
+
+    for level in 0..=255u8 {
+        assert_eq!(LINEAR.duty(Level(level)), level);
+    }
+}
+
+#[test]
+fn levels_from_fractions() {
+    assert_eq!(Level::from(0.0), Level::OFF);
+    assert_eq!(Level::from(0.5), Level(128));
+    assert_eq!(Level::from(1.0), Level::FULL);
+    assert_eq!(Level::from(-1.0), Level::OFF);
+    assert_eq!(Level::from(2.0), Level::FULL);
+}
+
+#[test]
+#[should_panic(expected = "positive and finite")]
+fn rejects_infinite_gamma() {
+    GammaTable::new(f32::INFINITY);
+}
+
+#[test]
+#[should_panic(expected = "positive and finite")]
+fn rejects_nan_gamma() {
+    GammaTable::new(f32::NAN);
+}
+
+#[test]
+#[should_panic(expected = "positive and finite")]
+fn rejects_zero_gamma() {
+    GammaTable::new(0.0);
+}
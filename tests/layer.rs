@@ -0,0 +1,115 @@
+//! Host tests for compositing frames from layers.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use embassy_time::Instant;
+use hakkaa::frame::Frame;
+use hakkaa::led::{Blend, Layer, LayerStack};
+
+fn art(art: &str) -> Frame {
+    Frame::from_art(art)
+}
+
+#[test]
+fn blend_modes() {
+    let below = art("##..##..");
+    let layer = Layer::new(art("#.#.#.#."));
+
+    assert_eq!(layer.apply(below), art("#.#.#.#."));
+    assert_eq!(layer.with_blend(Blend::Or).apply(below), art("###.###."));
+    assert_eq!(layer.with_blend(Blend::Xor).apply(below), art(".##..##."));
+}
+
+#[test]
+fn masks() {
+    let below = art("##..##..");
+    let mask = art("....####");
+
+    assert_eq!(
+        Layer::new(Frame::EMPTY).with_mask(mask).apply(below),
+        art("##......")
+    );
+    assert_eq!(
+        Layer::new(Frame::FULL)
+            .with_mask(mask)
+            .with_blend(Blend::Xor)
+            .apply(below),
+        art("##....##")
+    );
+}
+
+#[test]
+fn priorities() {
+    let mut layers: LayerStack = LayerStack::new();
+    assert_eq!(layers.render(), Frame::EMPTY);
+
+    let top = layers
+        .push(Layer::new(art("#.......")).with_priority(2))
+        .unwrap();
+    layers
+        .push(Layer::new(art(".#......")).with_priority(1))
+        .unwrap();
+    assert_eq!(layers.render(), art("#......."));
+
+    // Layers of the same priority stack in the order they were pushed.
+    layers
+        .push(Layer::new(art("..#.....")).with_priority(2))
+        .unwrap();
+    assert_eq!(layers.render(), art("..#....."));
+    assert_eq!(layers.len(), 3);
+
+    layers.remove(top);
+    layers.remove(top);
+    assert_eq!(layers.render(), art("..#....."));
+    assert_eq!(layers.len(), 2);
+}
+
+#[test]
+fn background_comes_back() {
+    let mut layers: LayerStack = LayerStack::new();
+    let background = layers
+        .push(Layer::new(art("#.......")).with_priority(0))
+        .unwrap();
+    layers
+        .push(
+            Layer::new(Frame::FULL)
+                .with_priority(1)
+                .with_expiry(Instant::from_millis(300)),
+        )
+        .unwrap();
+    layers
+        .push(
+            Layer::new(art("#......#"))
+                .with_mask(art("#......#"))
+                .with_priority(2)
+                .with_expiry(Instant::from_millis(100)),
+        )
+        .unwrap();
+    assert_eq!(layers.next_expiry(), Some(Instant::from_millis(100)));
+
+    // The background keeps animating below the notifications.
+    layers
+        .get_mut(background)
+        .unwrap()
+        .set_frame(art(".#......"));
+    assert_eq!(layers.render(), Frame::FULL);
+
+    layers.expire(Instant::from_millis(100));
+    assert_eq!(layers.next_expiry(), Some(Instant::from_millis(300)));
+    assert_eq!(layers.render(), Frame::FULL);
+
+    layers.expire(Instant::from_millis(300));
+    assert_eq!(layers.next_expiry(), None);
+    assert_eq!(layers.render(), art(".#......"));
+}
+
+#[test]
+fn full_stack() {
+    let mut layers: LayerStack<8, 2> = LayerStack::new();
+    layers.push(Layer::new(Frame::EMPTY)).unwrap();
+    layers.push(Layer::new(Frame::EMPTY)).unwrap();
+
+    let layer = Layer::new(Frame::FULL);
+    assert_eq!(layers.push(layer), Err(layer));
+    assert_eq!(layers.render(), Frame::EMPTY);
+}
@@ -0,0 +1,77 @@
+//! Host tests for mapping logical LEDs to physical ones.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use hakkaa::frame::Frame;
+use hakkaa::mapping::{Mapping, Orientation};
+
+const WIRING: Mapping = Mapping::new([7, 6, 5, 4, 0, 1, 2, 3]);
+
+fn art(art: &str) -> Frame {
+    Frame::from_art(art)
+}
+
+#[test]
+fn permutation() {
+    let frame = art("##.#....");
+
+    assert_eq!(WIRING.map(frame), art("....#.##"));
+    assert_eq!(WIRING.unmap(WIRING.map(frame)), frame);
+    for index in 0..8 {
+        assert_eq!(WIRING.logical(WIRING.physical(index)), index);
+    }
+    assert_eq!(Mapping::<8>::IDENTITY.map(frame), frame);
+}
+
+#[test]
+fn invalid_tables() {
+    assert_eq!(Mapping::try_new([0, 1, 2, 3, 4, 5, 6, 6]), None);
+    assert_eq!(Mapping::try_new([0, 1, 2, 3, 4, 5, 6, 8]), None);
+    assert_eq!(
+        Mapping::try_new([1, 0, 2, 3, 4, 5, 6, 7]),
+        Some(Mapping::new([1, 0, 2, 3, 4, 5, 6, 7]))
+    );
+}
+
+#[test]
+#[should_panic]
+fn invalid_table_panics() {
+    Mapping::new([0, 0]);
+}
+
+#[test]
+fn mirrored_and_composed() {
+    let frame = art("##.#....");
+    let mirrored = Mapping::IDENTITY.mirrored();
+
+    assert_eq!(mirrored.map(frame), frame.mirror());
+    assert_eq!(mirrored.mirrored(), Mapping::IDENTITY);
+    assert_eq!(WIRING.mirrored().map(frame), WIRING.map(frame.mirror()));
+    assert_eq!(mirrored.then(WIRING), WIRING.mirrored());
+    assert_eq!(WIRING.then(mirrored).map(frame), WIRING.map(frame).mirror());
+}
+
+#[test]
+fn orientations() {
+    let frame = art("##.#....");
+    let columns = [1, 2, 3];
+    let order = |orientation: Orientation| -> Vec<i32> {
+        orientation.columns(columns.iter()).copied().collect()
+    };
+
+    assert_eq!(Orientation::Upright.mapping(WIRING), WIRING);
+    assert_eq!(
+        Orientation::UpsideDown.mapping(WIRING).map(frame),
+        WIRING.map(frame.mirror())
+    );
+    assert_eq!(Orientation::Mirrored.mapping(WIRING), WIRING);
+    assert_eq!(
+        Orientation::MirroredUpsideDown.mapping(WIRING),
+        WIRING.mirrored()
+    );
+
+    assert_eq!(order(Orientation::Upright), [1, 2, 3]);
+    assert_eq!(order(Orientation::UpsideDown), [3, 2, 1]);
+    assert_eq!(order(Orientation::Mirrored), [3, 2, 1]);
+    assert_eq!(order(Orientation::MirroredUpsideDown), [1, 2, 3]);
+}
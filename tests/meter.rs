@@ -0,0 +1,105 @@
+//! Host tests for showing values on a meter.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use hakkaa::frame::Frame;
+use hakkaa::gamma::Level;
+use hakkaa::meter::{Meter, Scale, Style};
+
+fn show<const N: usize>(meter: Meter, value: f32, min: f32, max: f32) -> String {
+    meter.frame::<N>(value, min, max).to_string()
+}
+
+#[test]
+fn bar() {
+    let meter = Meter::new(Style::Bar);
+
+    assert_eq!(show::<8>(meter, 0.0, 0.0, 8.0), "........");
+    assert_eq!(show::<8>(meter, 2.4, 0.0, 8.0), "##......");
+    assert_eq!(show::<8>(meter, 2.6, 0.0, 8.0), "###.....");
+    assert_eq!(show::<8>(meter, 8.0, 0.0, 8.0), "########");
+    assert_eq!(show::<8>(meter, 42.0, 0.0, 8.0), "########");
+    assert_eq!(show::<8>(meter, -42.0, 0.0, 8.0), "........");
+}
+
+#[test]
+fn countdown_with_reversed_range() {
+    // A countdown from 60 seconds with the bar shrinking towards the end.
+    let meter = Meter::default();
+
+    assert_eq!(show::<6>(meter, 60.0, 0.0, 60.0), "######");
+    assert_eq!(show::<6>(meter, 20.0, 0.0, 60.0), "##....");
+    assert_eq!(show::<6>(meter, 20.0, 60.0, 0.0), "####..");
+}
+
+#[test]
+fn dot() {
+    let meter = Meter::new(Style::Dot);
+
+    assert_eq!(show::<8>(meter, 0.0, 0.0, 7.0), "#.......");
+    assert_eq!(show::<8>(meter, 3.4, 0.0, 7.0), "...#....");
+    assert_eq!(show::<8>(meter, 3.6, 0.0, 7.0), "....#...");
+    assert_eq!(show::<8>(meter, 7.0, 0.0, 7.0), ".......#");
+}
+
+#[test]
+fn centre_out() {
+    let meter = Meter::new(Style::CentreOut);
+
+    assert_eq!(show::<8>(meter, 0.0, 0.0, 4.0), "........");
+    assert_eq!(show::<8>(meter, 1.0, 0.0, 4.0), "...##...");
+    assert_eq!(show::<8>(meter, 4.0, 0.0, 4.0), "########");
+    assert_eq!(show::<5>(meter, 1.0, 0.0, 3.0), "..#..");
+    assert_eq!(show::<5>(meter, 2.0, 0.0, 3.0), ".###.");
+}
+
+#[test]
+fn logarithmic() {
+    let meter = Meter::default().with_scale(Scale::Logarithmic);
+
+    // Each LED stands for a factor of two from 1 to 256.
+    assert_eq!(show::<8>(meter, 1.0, 1.0, 256.0), "........");
+    assert_eq!(show::<8>(meter, 4.0, 1.0, 256.0), "##......");
+    assert_eq!(show::<8>(meter, 64.0, 1.0, 256.0), "######..");
+    assert_eq!(show::<8>(meter, 0.0, 1.0, 256.0), "........");
+    assert_eq!(show::<8>(meter, 1.0, 0.0, 256.0), "........");
+}
+
+#[test]
+fn logarithmic_invalid_values_and_ranges() {
+    let meter = Meter::default().with_scale(Scale::Logarithmic);
+
+    assert_eq!(show::<8>(meter, f32::INFINITY, 1.0, 256.0), "########");
+    assert_eq!(show::<8>(meter, f32::NEG_INFINITY, 1.0, 256.0), "........");
+    assert_eq!(show::<8>(meter, f32::NAN, 1.0, 256.0), "........");
+    assert_eq!(show::<8>(meter, 4.0, 1.0, f32::INFINITY), "........");
+    assert_eq!(
+        show::<8>(meter, f32::INFINITY, 1.0, f32::INFINITY),
+        "########"
+    );
+    assert_eq!(show::<8>(meter, 4.0, 1.0, f32::NAN), "........");
+    assert_eq!(show::<8>(meter, 4.0, 1.0, 0.0), "########");
+    assert_eq!(show::<8>(meter, -3.0, 1.0, -2.0), "........");
+    assert_eq!(show::<8>(meter, 4.0, 8.0, 2.0), "########");
+}
+
+#[test]
+fn empty_range() {
+    let meter = Meter::default();
+
+    assert_eq!(show::<4>(meter, 1.0, 2.0, 2.0), "....");
+    assert_eq!(show::<4>(meter, 2.0, 2.0, 2.0), "####");
+}
+
+#[test]
+fn sub_step_levels() {
+    let bar: [Level; 4] = Meter::default().levels(1.5, 0.0, 4.0);
+    let dot: [Level; 4] = Meter::new(Style::Dot).levels(1.25, 0.0, 3.0);
+
+    assert_eq!(bar, [Level::FULL, Level(128), Level::OFF, Level::OFF]);
+    assert_eq!(dot, [Level::OFF, Level(191), Level(64), Level::OFF]);
+    assert_eq!(
+        Frame::from(bar.map(|level| level > Level::OFF)),
+        Frame::<4>::from_art("##..")
+    );
+}
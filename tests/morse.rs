@@ -0,0 +1,143 @@
+//! Host tests for encoding text in Morse code and playing it on mock LEDs.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use embassy_time::{Duration, Instant};
+use hakkaa::animation::Animation;
+use hakkaa::frame::Frame;
+use hakkaa::led::Storeys;
+use hakkaa::mock::{self, MockSwitch, Simulation, Transition};
+use hakkaa::morse::{self, Timing};
+
+/// Renders Morse code with `=` for each unit the LED is on and `.` for each unit it is off.
+fn render(text: &str, timing: Timing) -> String {
+    let unit = timing.unit().as_micros();
+    morse::encode(text, timing)
+        .map(|step| {
+            let symbol = if step.frame { "=" } else { "." };
+            symbol.repeat((step.duration.as_micros() / unit) as usize)
+        })
+        .collect()
+}
+
+#[test]
+fn itu_timing() {
+    let timing = Timing::new(20);
+
+    assert_eq!(timing.unit(), Duration::from_millis(60));
+    assert_eq!(render("sos", timing), "=.=.=...===.===.===...=.=.=");
+    assert_eq!(render("a b", timing), "=.===.......===.=.=.=");
+}
+
+#[test]
+fn paris_takes_a_minute_per_word() {
+    let timing = Timing::new(12);
+    let paris = morse::encode("PARIS PARIS", timing).total_duration() + timing.word_gap();
+
+    assert_eq!(paris, Duration::from_secs(10));
+}
+
+#[test]
+fn farnsworth_spacing() {
+    let timing = Timing::new(18).with_farnsworth(5);
+
+    // Characters keep their speed, only the gaps between them grow.
+    let elements = |timing| {
+        morse::encode("paris", timing)
+            .filter(|step| step.frame)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(timing.unit(), Timing::new(18).unit());
+    assert_eq!(elements(timing), elements(Timing::new(18)));
+    assert!(timing.char_gap() > timing.unit() * 3);
+    // The whole word takes a fifth of a minute.
+    let paris = morse::encode("PARIS", timing).total_duration() + timing.word_gap();
+    assert!(paris.as_millis().abs_diff(12_000) < 10, "{paris}");
+}
+
+#[test]
+fn farnsworth_above_character_speed() {
+    let timing = Timing::new(10).with_farnsworth(20);
+    assert_eq!(timing.char_gap(), timing.unit() * 3);
+    assert_eq!(timing.word_gap(), timing.unit() * 7);
+}
+
+#[test]
+fn whitespace_and_unknown_characters() {
+    let timing = Timing::new(20);
+
+    assert_eq!(render("  e \t\n e  ", timing), render("e e", timing));
+    assert_eq!(render("e~e", timing), render("ee", timing));
+    assert_eq!(render("E", timing), render("e", timing));
+    assert_eq!(morse::encode("~ ", timing).count(), 0);
+}
+
+#[test]
+fn codes() {
+    assert_eq!(morse::code('q'), Some("--.-"));
+    assert_eq!(morse::code('0'), Some("-----"));
+    assert_eq!(morse::code('?'), Some("..--.."));
+    assert_eq!(morse::code('ä'), None);
+}
+
+#[test]
+fn play_on_switch() {
+    let simulation = Simulation::new();
+    let mut led = MockSwitch::new();
+    let recorder = led.recorder();
+
+    let done = simulation.run_for(
+        morse::play(&mut led, morse::encode("at", Timing::new(12))),
+        Duration::from_secs(2),
+    );
+
+    assert_eq!(done, Some(()));
+    let transitions: Vec<(bool, u64)> = recorder
+        .transitions()
+        .iter()
+        .map(|transition| (transition.is_on(), transition.at.as_millis()))
+        .collect();
+    assert_eq!(
+        transitions,
+        [
+            (true, 0),
+            (false, 100),
+            (true, 200),
+            (false, 500),
+            (true, 800),
+            (false, 1_100),
+        ]
+    );
+}
+
+#[test]
+fn play_on_storeys() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+    let frame = Frame::from_art("#......#");
+
+    let done = simulation.run_for(
+        storeys.play(morse::encode("i", Timing::new(12)).frames(frame)),
+        Duration::from_secs(1),
+    );
+
+    assert_eq!(done, Some(()));
+    let dots = [
+        Transition {
+            at: Instant::from_millis(0),
+            brightness: 255,
+        },
+        Transition {
+            at: Instant::from_millis(100),
+            brightness: 0,
+        },
+        Transition {
+            at: Instant::from_millis(200),
+            brightness: 255,
+        },
+    ];
+    assert_eq!(recorders[0].transitions(), dots);
+    assert_eq!(recorders[7].transitions(), dots);
+    assert!(recorders[1].transitions().is_empty());
+}
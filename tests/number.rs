@@ -0,0 +1,53 @@
+//! Host tests for showing numbers.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use hakkaa::number::{self, Number};
+
+fn pages<const N: usize>(number: Number) -> Vec<String> {
+    number.pages::<N>().map(|page| page.to_string()).collect()
+}
+
+#[test]
+fn binary() {
+    assert_eq!(number::binary(0).to_string(), "........");
+    assert_eq!(number::binary(1).to_string(), "#.......");
+    assert_eq!(number::binary(0b1010_0000).to_string(), ".....#.#");
+}
+
+#[test]
+fn bcd() {
+    assert_eq!(number::bcd(7).unwrap().to_string(), "###.....");
+    assert_eq!(number::bcd(99).unwrap().to_string(), "#..##..#");
+    assert_eq!(number::bcd(100), None);
+    assert_eq!(Number::from_bcd(12345).bits(), 0x12345);
+    assert_eq!(pages::<8>(Number::from_bcd(1234)), [".#..#...", "..#.##.."]);
+}
+
+#[test]
+fn gray_code_changes_one_led_at_a_time() {
+    for value in 0..u8::MAX {
+        let changed = number::gray(value) ^ number::gray(value + 1);
+        assert_eq!(changed.count_on(), 1, "{value}");
+    }
+    assert_eq!(Number::from_u16(0x8000).gray().bits(), 0xc000);
+}
+
+#[test]
+fn twos_complement() {
+    assert_eq!(number::twos_complement(1).to_string(), "#.......");
+    assert_eq!(number::twos_complement(-2).to_string(), ".#######");
+    assert_eq!(number::twos_complement(i8::MIN).to_string(), ".......#");
+    assert_eq!(pages::<8>(Number::from_i16(-256)), ["########", "........"]);
+}
+
+#[test]
+fn pages_most_significant_first() {
+    assert_eq!(
+        pages::<8>(Number::from_u32(0x8000_0001)),
+        [".......#", "........", "........", "#......."]
+    );
+    assert_eq!(pages::<16>(Number::from_u16(0x8001)), ["#..............#"]);
+    // The most significant page is padded for a width not being a multiple of the LEDs.
+    assert_eq!(pages::<6>(Number::from_u8(0xff)), ["##....", "######"]);
+}
@@ -0,0 +1,97 @@
+//! Host tests for limiting the current drawn by the LEDs.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use embassy_time::Duration;
+use hakkaa::frame::Frame;
+use hakkaa::mock::{self, Simulation};
+use hakkaa::power::{Budget, Strategy};
+use hakkaa::pwm::{Duties, SoftPwm};
+
+fn art(art: &str) -> Frame {
+    Frame::from_art(art)
+}
+
+#[test]
+fn max_leds() {
+    assert_eq!(Budget::new(20.0, 5.0).max_leds(), 4);
+    assert_eq!(Budget::new(22.0, 5.0).max_leds(), 4);
+    assert_eq!(Budget::new(20.0, 5.0).with_reserve(8.0).max_leds(), 2);
+    assert_eq!(Budget::new(20.0, 5.0).with_reserve(30.0).max_leds(), 0);
+    assert_eq!(Budget::new(20.0, 5.0).current(&[255, 255, 51]), 11.0);
+}
+
+#[test]
+fn cap_frames() {
+    let budget = Budget::new(10.0, 5.0);
+
+    assert_eq!(budget.limit_frame(art("#.#.#.#.")), art("#.#....."));
+    assert_eq!(budget.limit_frame(art("......#.")), art("......#."));
+    assert_eq!(
+        Budget::new(0.0, 5.0).limit_frame(Frame::<8>::FULL),
+        Frame::EMPTY
+    );
+}
+
+#[test]
+fn groups() {
+    let budget = Budget::new(10.0, 5.0);
+    let groups = |frame| budget.groups(frame).collect::<Vec<Frame>>();
+
+    assert_eq!(groups(art("###.#...")), [art("##......"), art("..#.#...")]);
+    assert_eq!(groups(art("..#.....")), [art("..#.....")]);
+    assert_eq!(groups(Frame::EMPTY), [Frame::EMPTY]);
+    assert_eq!(
+        Budget::new(0.0, 5.0)
+            .groups(Frame::<8>::FULL)
+            .collect::<Vec<_>>(),
+        [Frame::EMPTY]
+    );
+}
+
+#[test]
+fn limit_duties() {
+    let cap = Budget::new(10.0, 5.0);
+    let scale = cap.with_strategy(Strategy::Scale);
+
+    // Within the budget.
+    assert_eq!(cap.limit_duties([255, 0, 255, 0]), [255, 0, 255, 0]);
+    assert_eq!(scale.limit_duties([200, 100, 200, 0]), [200, 100, 200, 0]);
+
+    // Beyond the budget. Smaller duty cycles later on still fit in when capping.
+    assert_eq!(cap.limit_duties([255, 200, 255, 50]), [255, 200, 0, 50]);
+    assert_eq!(
+        scale.limit_duties([255, 255, 255, 255]),
+        [127, 127, 127, 127]
+    );
+}
+
+#[test]
+fn soft_pwm_multiplexes() {
+    static DUTIES: Duties<4> = Duties::new();
+
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<4>();
+    let budget = Budget::new(10.0, 5.0).with_strategy(Strategy::Multiplex(Duration::MIN));
+    let mut pwm = SoftPwm::new(switches, &DUTIES)
+        .with_refresh_rate(100)
+        .with_budget(budget);
+    DUTIES.set(0, 255);
+    DUTIES.set(1, 255);
+    DUTIES.set(2, 255);
+
+    simulation.run_for(pwm.run(), Duration::from_millis(14));
+
+    // The first two LEDs take turns with the third one, each group for half of the 10 ms period.
+    let at = |recorder: &mock::Recorder| -> Vec<(u64, bool)> {
+        recorder
+            .transitions()
+            .iter()
+            .map(|transition| (transition.at.as_millis(), transition.is_on()))
+            .collect()
+    };
+    assert_eq!(at(&recorders[0]), [(0, true), (5, false), (10, true)]);
+    assert_eq!(at(&recorders[1]), [(0, true), (5, false), (10, true)]);
+    assert_eq!(at(&recorders[2]), [(5, true), (10, false)]);
+    assert_eq!(recorders[3].transitions(), []);
+}
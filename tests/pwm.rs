@@ -0,0 +1,74 @@
+//! Host tests for the software PWM scheduling.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use embassy_time::Duration;
+use hakkaa::pwm::{Duties, Edge, Schedule};
+use hakkaa::switch::{Dimmable, Switch};
+
+#[test]
+fn all_off() {
+    let schedule = Schedule::new(&[0; 9]);
+
+    assert_eq!(schedule.on(), 0);
+    assert!(schedule.edges().is_empty());
+}
+
+#[test]
+fn fully_on_leds_have_no_edge() {
+    let schedule = Schedule::new(&[255, 0, 255]);
+
+    assert_eq!(schedule.on(), 0b101);
+    assert!(schedule.edges().is_empty());
+}
+
+#[test]
+fn edges_are_sorted_and_shared() {
+    let schedule = Schedule::new(&[200, 10, 0, 200, 255, 100]);
+
+    assert_eq!(schedule.on(), 0b111011);
+    assert_eq!(
+        schedule.edges(),
+        &[
+            Edge { at: 10, mask: 0b10 },
+            Edge {
+                at: 100,
+                mask: 0b100000
+            },
+            Edge {
+                at: 200,
+                mask: 0b1001
+            },
+        ]
+    );
+}
+
+#[test]
+fn edge_offset() {
+    let period = Duration::from_micros(5100);
+
+    assert_eq!(
+        Edge { at: 1, mask: 1 }.offset(period),
+        Duration::from_micros(20)
+    );
+    assert_eq!(
+        Edge { at: 51, mask: 1 }.offset(period),
+        Duration::from_micros(1020)
+    );
+    assert_eq!(Edge { at: 255, mask: 1 }.offset(period), period);
+}
+
+#[test]
+fn channels_set_duties() {
+    let duties = Duties::<3>::new();
+    let [mut first, _, mut third] = duties.channels();
+
+    first.switch_on();
+    third.set_brightness(42);
+    assert_eq!(duties.all(), [255, 0, 42]);
+    assert!(first.is_on());
+
+    first.toggle();
+    assert_eq!(duties.all(), [0, 0, 42]);
+    assert_eq!(third.brightness(), 42);
+}
@@ -0,0 +1,194 @@
+//! Host tests for controlling the LEDs through the LED service with mock LEDs.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use core::future::Future;
+
+use embassy_futures::join::join;
+use embassy_time::{Duration, Instant, Timer};
+use hakkaa::animation::{Keyframes, LoopMode, Step};
+use hakkaa::frame::Frame;
+use hakkaa::led::Storeys;
+use hakkaa::mock::{self, MockSwitch, Recorder, Simulation, Transition};
+use hakkaa::service::{LedChannel, LedHandle, LedService};
+
+const STEP: Duration = Duration::from_millis(100);
+
+static STEPS: [Step; 3] = [
+    Step::new(Frame::D1, STEP),
+    Step::new(Frame::D2, STEP),
+    Step::new(Frame::D3, STEP),
+];
+
+fn on(millis: u64) -> Transition {
+    Transition {
+        at: Instant::from_millis(millis),
+        brightness: 255,
+    }
+}
+
+fn off(millis: u64) -> Transition {
+    Transition {
+        at: Instant::from_millis(millis),
+        brightness: 0,
+    }
+}
+
+/// Runs an LED service together with `client` for `duration`. Returns the recorders of the storey
+/// LEDs and the LED on the ESP32-C3 board.
+fn run<'a, F: Future<Output = ()>>(
+    channel: &'a LedChannel,
+    client: impl FnOnce(LedHandle<'a>) -> F,
+    duration: Duration,
+) -> ([Recorder; 8], Recorder) {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let esp_led = MockSwitch::new();
+    let esp_recorder = esp_led.recorder();
+    let service = LedService::new(Storeys::new(switches), esp_led, channel);
+    let client = client(service.handle());
+
+    // The client goes first so that the service picks up its commands in the same poll.
+    simulation.run_for(join(client, service.run()), duration);
+    (recorders, esp_recorder)
+}
+
+#[test]
+fn set_frame_and_stop() {
+    let channel = LedChannel::new();
+    let (recorders, esp_recorder) = run(
+        &channel,
+        |leds| async move {
+            leds.set_frame(Frame::from_art("#.#.....")).await;
+            leds.set_esp_led(true).await;
+            Timer::after(STEP).await;
+            leds.stop().await;
+        },
+        Duration::from_millis(200),
+    );
+
+    assert_eq!(recorders[0].transitions(), [on(0), off(100)]);
+    assert_eq!(recorders[1].transitions(), []);
+    assert_eq!(recorders[2].transitions(), [on(0), off(100)]);
+    assert_eq!(esp_recorder.transitions(), [on(0)]);
+}
+
+#[test]
+fn play_once() {
+    let channel = LedChannel::new();
+    let (recorders, _) = run(
+        &channel,
+        |leds| async move { leds.play(&STEPS).await },
+        Duration::from_millis(500),
+    );
+
+    assert_eq!(recorders[0].transitions(), [on(0), off(100)]);
+    assert_eq!(recorders[1].transitions(), [on(100), off(200)]);
+    assert_eq!(recorders[2].transitions(), [on(200)]);
+}
+
+#[test]
+fn play_forever() {
+    let channel = LedChannel::new();
+    let (recorders, _) = run(
+        &channel,
+        |leds| async move { leds.play_forever(&STEPS).await },
+        Duration::from_millis(450),
+    );
+
+    assert_eq!(
+        recorders[0].transitions(),
+        [on(0), off(100), on(300), off(400)]
+    );
+    assert_eq!(recorders[2].transitions(), [on(200), off(300)]);
+}
+
+#[test]
+fn play_keyframes() {
+    static PING_PONG: Keyframes = Keyframes::new(&STEPS).with_loop(LoopMode::PingPong);
+
+    let channel = LedChannel::new();
+    let (recorders, _) = run(
+        &channel,
+        |leds| async move { leds.play_keyframes(PING_PONG).await },
+        Duration::from_millis(450),
+    );
+
+    assert_eq!(
+        recorders[1].transitions(),
+        [on(100), off(200), on(300), off(400)]
+    );
+    assert_eq!(recorders[0].transitions(), [on(0), off(100), on(400)]);
+}
+
+#[test]
+fn timeless_loop_plays_once() {
+    static TIMELESS: Keyframes = Keyframes::new(&[
+        Step::new(Frame::D1, Duration::MIN),
+        Step::new(Frame::D3, Duration::MIN),
+    ])
+    .with_loop(LoopMode::Forever);
+
+    let channel = LedChannel::new();
+    let (recorders, _) = run(
+        &channel,
+        |leds| async move {
+            leds.play_keyframes(TIMELESS).await;
+            Timer::after(STEP).await;
+            leds.set_frame(Frame::D5).await;
+        },
+        Duration::from_millis(200),
+    );
+
+    // The service keeps processing commands instead of spinning through the frames.
+    assert_eq!(recorders[0].transitions(), [on(0), off(1)]);
+    assert_eq!(recorders[2].transitions(), [on(1), off(100)]);
+    assert_eq!(recorders[4].transitions(), [on(100)]);
+}
+
+#[test]
+fn commands_from_multiple_tasks() {
+    let channel = LedChannel::new();
+    let (recorders, _) = run(
+        &channel,
+        |leds| async move {
+            let other = leds;
+            join(
+                async {
+                    leds.set_frame(Frame::D1).await;
+                },
+                async {
+                    Timer::after(STEP).await;
+                    other.set_frame(Frame::D2).await;
+                },
+            )
+            .await;
+        },
+        Duration::from_millis(200),
+    );
+
+    assert_eq!(recorders[0].transitions(), [on(0), off(100)]);
+    assert_eq!(recorders[1].transitions(), [on(100)]);
+}
+
+#[test]
+fn flash_pauses_animation() {
+    let channel = LedChannel::new();
+    let (recorders, _) = run(
+        &channel,
+        |leds| async move {
+            leds.play(&STEPS).await;
+            Timer::after(Duration::from_millis(150)).await;
+            leds.flash(Frame::D8, Duration::from_millis(50)).await;
+        },
+        Duration::from_millis(500),
+    );
+
+    // The second step starts over after the flash.
+    assert_eq!(recorders[7].transitions(), [on(150), off(200)]);
+    assert_eq!(
+        recorders[1].transitions(),
+        [on(100), off(150), on(200), off(300)]
+    );
+    assert_eq!(recorders[2].transitions(), [on(300)]);
+}
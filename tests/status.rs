@@ -0,0 +1,133 @@
+//! Host tests for reporting the status with a mock status LED.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use core::future::Future;
+
+use embassy_futures::join::join;
+use embassy_time::{Duration, Instant, Timer};
+use hakkaa::mock::{MockSwitch, Simulation, Transition};
+use hakkaa::status::{Status, StatusHandle, StatusLed, StatusSignal};
+
+fn on(millis: u64) -> Transition {
+    Transition {
+        at: Instant::from_millis(millis),
+        brightness: 255,
+    }
+}
+
+fn off(millis: u64) -> Transition {
+    Transition {
+        at: Instant::from_millis(millis),
+        brightness: 0,
+    }
+}
+
+/// Runs a status LED together with `client` for `duration`. Returns the transitions of the LED.
+fn run<'a, F: Future<Output = ()>>(
+    signal: &'a StatusSignal,
+    client: impl FnOnce(StatusHandle<'a>) -> F,
+    duration: Duration,
+) -> Vec<Transition> {
+    let simulation = Simulation::new();
+    let led = MockSwitch::new();
+    let recorder = led.recorder();
+    let status_led = StatusLed::new(led, signal);
+    let client = client(status_led.handle());
+
+    simulation.run_for(join(client, status_led.run()), duration);
+    recorder.transitions()
+}
+
+fn lit(status: Status) -> Vec<(bool, u64)> {
+    status
+        .blinks()
+        .map(|step| (step.frame, step.duration.as_millis()))
+        .collect()
+}
+
+#[test]
+fn error_codes() {
+    assert_eq!(lit(Status::Error(0)), [(false, 1_500)]);
+    assert_eq!(lit(Status::Error(1)), [(true, 600), (false, 1_500)]);
+    assert_eq!(
+        lit(Status::Error(2)),
+        [(true, 600), (false, 300), (true, 200), (false, 1_500)]
+    );
+    assert_eq!(
+        Status::Error(9).blinks().filter(|step| step.frame).count(),
+        9
+    );
+}
+
+#[test]
+fn patterns_repeat_without_drift() {
+    let signal = StatusSignal::new();
+    let transitions = run(
+        &signal,
+        |status| async move { status.heartbeat() },
+        Duration::from_millis(4_100),
+    );
+
+    assert_eq!(
+        transitions,
+        [
+            on(0),
+            off(60),
+            on(200),
+            off(260),
+            on(2_000),
+            off(2_060),
+            on(2_200),
+            off(2_260),
+            on(4_000),
+            off(4_060),
+        ]
+    );
+}
+
+#[test]
+fn new_status_starts_after_gap() {
+    let signal = StatusSignal::new();
+    let transitions = run(
+        &signal,
+        |status| async move {
+            status.attention();
+            Timer::at(Instant::from_millis(1_500)).await;
+            status.error(2);
+        },
+        Duration::from_millis(3_000),
+    );
+
+    assert_eq!(
+        transitions,
+        [
+            // Coming from off, the first status starts right away.
+            on(0),
+            off(800),
+            on(1_000),
+            // The error code takes over in the middle of a blink, which gets cut short for a gap.
+            off(1_500),
+            on(1_800),
+            off(2_400),
+            on(2_700),
+            off(2_900),
+        ]
+    );
+}
+
+#[test]
+fn same_status_keeps_going() {
+    let signal = StatusSignal::new();
+    let transitions = run(
+        &signal,
+        |status| async move {
+            status.busy();
+            Timer::at(Instant::from_millis(150)).await;
+            status.busy();
+        },
+        Duration::from_millis(450),
+    );
+
+    assert_eq!(transitions, [on(0), off(100), on(200), off(300), on(400)]);
+}
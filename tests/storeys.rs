@@ -0,0 +1,446 @@
+//! Host tests for the storey LED patterns with mock LEDs.
+//!
+//! See "Tests on the host" in the README for running them.
+
+use core::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use embassy_time::{Duration, Instant};
+use embedded_hal::digital::{ErrorType, OutputPin};
+use hakkaa::animation::{self, Animation, Keyframes, LoopMode, Step};
+use hakkaa::frame::Frame;
+use hakkaa::gamma::{Level, GAMMA};
+use hakkaa::led::{Layer, LayerStack, Storeys};
+use hakkaa::mapping::Mapping;
+use hakkaa::meter::Meter;
+use hakkaa::mock::{self, Simulation, Transition};
+use hakkaa::number::Number;
+use hakkaa::power::{Budget, Strategy, MIN_SLOT};
+use hakkaa::switch::{LowActiveSwitch, Port};
+use hakkaa::transition::Transition as FrameTransition;
+
+fn on(millis: u64) -> Transition {
+    Transition {
+        at: Instant::from_millis(millis),
+        brightness: 255,
+    }
+}
+
+fn off(millis: u64) -> Transition {
+    Transition {
+        at: Instant::from_millis(millis),
+        brightness: 0,
+    }
+}
+
+#[test]
+fn set_pattern() {
+    let _simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+
+    storeys.set_pattern(0b1000_0101);
+
+    assert_eq!(storeys.pattern(), 0b1000_0101);
+    for (n, recorder) in recorders.iter().enumerate() {
+        let expected = match n {
+            0 | 2 | 7 => vec![on(0)],
+            _ => vec![],
+        };
+        assert_eq!(recorder.transitions(), expected, "D{}", n + 1);
+    }
+}
+
+#[test]
+fn set_frame() {
+    let _simulation = Simulation::new();
+    let (switches, _) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+
+    storeys.set_frame(Frame::from_art("#.#....#"));
+
+    assert_eq!(storeys.pattern(), 0b1000_0101);
+    assert_eq!(storeys.frame().to_string(), "#.#....#");
+}
+
+#[test]
+fn set_pattern_twelve_leds() {
+    let _simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<12>();
+    let mut storeys: Storeys<_, 12> = Storeys::new(switches);
+
+    storeys.set_pattern(0b1001_0000_0001u16);
+
+    assert_eq!(storeys.pattern(), 0b1001_0000_0001);
+    for (n, recorder) in recorders.iter().enumerate() {
+        let expected = match n {
+            0 | 8 | 11 => vec![on(0)],
+            _ => vec![],
+        };
+        assert_eq!(recorder.transitions(), expected, "D{}", n + 1);
+    }
+}
+
+#[test]
+fn cycle() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+
+    simulation.run_for(
+        storeys.cycle(Duration::from_millis(100)),
+        Duration::from_millis(850),
+    );
+
+    assert_eq!(recorders[0].transitions(), [on(0), off(100), on(800)]);
+    assert_eq!(recorders[1].transitions(), [on(100), off(200)]);
+    assert_eq!(recorders[7].transitions(), [on(700), off(800)]);
+}
+
+#[test]
+fn blink() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+
+    simulation.run_for(
+        storeys.blink(Duration::from_millis(250)),
+        Duration::from_secs(1),
+    );
+
+    for recorder in recorders.iter() {
+        assert_eq!(
+            recorder.transitions(),
+            [on(0), off(250), on(500), off(750), on(1000)]
+        );
+    }
+}
+
+#[test]
+fn cycle_six_leds() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<6>();
+    let mut storeys = Storeys::new(switches);
+
+    simulation.run_for(
+        storeys.cycle(Duration::from_millis(100)),
+        Duration::from_millis(650),
+    );
+
+    assert_eq!(recorders[0].transitions(), [on(0), off(100), on(600)]);
+    assert_eq!(recorders[5].transitions(), [on(500), off(600)]);
+}
+
+#[test]
+fn play_finite_animation() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+    let animation = animation::cycle(Duration::from_millis(100))
+        .reverse()
+        .limit(Duration::from_millis(250));
+
+    let done = simulation.run_for(storeys.play(animation), Duration::from_secs(1));
+
+    assert_eq!(done, Some(()));
+    assert_eq!(
+        recorders[5].transitions().last().unwrap().at,
+        Instant::from_millis(200)
+    );
+    assert_eq!(storeys.frame(), Frame::D6);
+}
+
+#[test]
+fn play_keyframes_without_drift() {
+    static BLINK: [Step; 2] = [
+        Step::new(Frame::D1, Duration::from_millis(10)),
+        Step::new(Frame::EMPTY, Duration::from_millis(10)),
+    ];
+    // Timers expire up to 2 ms late at this resolution.
+    let simulation = Simulation::new().with_resolution(Duration::from_millis(3));
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+    let keyframes = Keyframes::new(&BLINK).with_loop(LoopMode::Forever);
+
+    let done = simulation.run_for(storeys.play_keyframes(keyframes), Duration::from_secs(60));
+
+    assert_eq!(done, None);
+    let transitions = recorders[0].transitions();
+    assert_eq!(transitions.len(), 6_001);
+    for (n, transition) in transitions.iter().enumerate() {
+        let late = transition.at - Instant::from_millis(n as u64 * 10);
+        assert!(late < Duration::from_millis(3), "{n}: {late}");
+    }
+}
+
+#[test]
+fn show_level_dimmed() {
+    let _simulation = Simulation::new();
+    let (switches, _) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+
+    storeys.show_level(3.0, 0.0, 8.0);
+    assert_eq!(storeys.frame(), Frame::from_art("###....."));
+
+    // The fourth LED shows the half step with the gamma corrected duty cycle for half brightness.
+    storeys.show_level_dimmed(3.5, 0.0, 8.0, Meter::default());
+    let brightness = storeys.brightness();
+    assert_eq!(brightness[..3], [255; 3]);
+    assert_eq!(brightness[3], GAMMA.duty(Level(128)));
+    assert_eq!(brightness[4..], [0; 4]);
+}
+
+#[test]
+fn show_number() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+    let mut marker = mock::MockSwitch::new();
+    let marker_recorder = marker.recorder();
+
+    let done = simulation.run_for(
+        storeys.show_number(
+            Number::from_u16(0x0102),
+            &mut marker,
+            Duration::from_millis(400),
+        ),
+        Duration::from_secs(2),
+    );
+
+    assert_eq!(done, Some(()));
+    assert_eq!(marker_recorder.transitions(), [on(0), off(400)]);
+    assert_eq!(recorders[0].transitions(), [on(0), off(400)]);
+    assert_eq!(recorders[1].transitions(), [on(500), off(900)]);
+    assert_eq!(Instant::now(), Instant::from_millis(1000));
+}
+
+#[test]
+fn transition_to() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+    storeys.set_frame(Frame::from_art("##......"));
+
+    let done = simulation.run_for(
+        storeys.transition_to(
+            Frame::from_art("#.#....."),
+            FrameTransition::crossfade(Duration::from_millis(400)),
+        ),
+        Duration::from_secs(1),
+    );
+
+    // The crossfade updates the brightness every 20 ms starting right away. The last step switches
+    // to the new frame.
+    assert_eq!(done, Some(()));
+    assert_eq!(recorders[0].transitions(), [on(0)]);
+    assert_eq!(recorders[1].transitions()[2].at, Instant::from_millis(20));
+    assert_eq!(recorders[1].transitions().last(), Some(&off(380)));
+    assert_eq!(recorders[2].transitions().last(), Some(&on(380)));
+    assert_eq!(storeys.frame(), Frame::from_art("#.#....."));
+    assert_eq!(Instant::now(), Instant::from_millis(400));
+}
+
+#[test]
+fn show_layers() {
+    let simulation = Simulation::new();
+    let (switches, _) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+    let mut layers: LayerStack = LayerStack::new();
+    layers.push(Layer::new(Frame::D1)).unwrap();
+    layers
+        .push(
+            Layer::new(Frame::D8)
+                .with_priority(1)
+                .with_timeout(Duration::from_millis(100)),
+        )
+        .unwrap();
+
+    storeys.show_layers(&mut layers);
+    assert_eq!(storeys.frame(), Frame::D8);
+
+    simulation.advance(Duration::from_millis(100));
+    storeys.show_layers(&mut layers);
+    assert_eq!(storeys.frame(), Frame::D1);
+    assert_eq!(layers.len(), 1);
+}
+
+#[test]
+fn mapping() {
+    let _simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches).with_mapping(Mapping::new([3, 2, 1, 0, 4, 5, 6, 7]));
+
+    storeys.set_frame(Frame::from_art("#......#"));
+    assert!(recorders[3].transitions()[0].is_on());
+    assert!(recorders[7].transitions()[0].is_on());
+    assert_eq!(storeys.frame(), Frame::from_art("#......#"));
+    assert_eq!(storeys.pattern(), 0b1000_0001);
+
+    // The LEDs keep showing the same frame when the mapping changes.
+    storeys.set_mapping(Mapping::IDENTITY.mirrored());
+    assert_eq!(storeys.frame(), Frame::from_art("#......#"));
+    assert_eq!(recorders[3].transitions().len(), 2);
+    assert!(recorders[0].transitions()[0].is_on());
+
+    storeys.set_pattern(0b0000_0010);
+    assert_eq!(storeys.frame(), Frame::D2);
+    assert!(recorders[6].transitions()[0].is_on());
+}
+
+#[test]
+fn budget() {
+    let _simulation = Simulation::new();
+    let (switches, _) = mock::switches::<8>();
+    let budget = Budget::new(20.0, 5.0).with_reserve(5.0);
+    let mut storeys = Storeys::new(switches).with_budget(budget);
+
+    storeys.all_on();
+    assert_eq!(storeys.frame(), Frame::from_art("###....."));
+    storeys.set_pattern(0b1010_1010);
+    assert_eq!(storeys.frame(), Frame::from_art(".#.#.#.."));
+
+    storeys.set_levels([Level::FULL; 8]);
+    assert_eq!(storeys.brightness(), [255, 255, 255, 0, 0, 0, 0, 0]);
+
+    let mut storeys =
+        Storeys::new(storeys.free()).with_budget(budget.with_strategy(Strategy::Scale));
+    storeys.set_brightness([255; 8]);
+    assert_eq!(storeys.brightness(), [95; 8]);
+}
+
+#[test]
+fn budget_multiplex() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let budget =
+        Budget::new(20.0, 5.0).with_strategy(Strategy::Multiplex(Duration::from_millis(2)));
+    let mut storeys = Storeys::new(switches).with_budget(budget);
+
+    let done = simulation.run_for(
+        storeys.play(animation::frames(
+            &[Frame::FULL, Frame::D1],
+            Duration::from_millis(8),
+        )),
+        Duration::from_secs(1),
+    );
+
+    // Half of the LEDs are lit at a time during the first frame.
+    assert_eq!(done, Some(()));
+    assert_eq!(
+        recorders[0].transitions(),
+        [on(0), off(2), on(4), off(6), on(8)]
+    );
+    assert_eq!(recorders[7].transitions(), [on(2), off(4), on(6), off(8)]);
+    assert_eq!(storeys.frame(), Frame::D1);
+}
+
+#[test]
+fn budget_multiplex_zero_slot() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let budget = Budget::new(20.0, 5.0).with_strategy(Strategy::Multiplex(Duration::from_ticks(0)));
+    let mut storeys = Storeys::new(switches).with_budget(budget);
+
+    let done = simulation.run_for(
+        storeys.play(animation::frames(&[Frame::FULL], Duration::from_millis(3))),
+        Duration::from_secs(1),
+    );
+
+    // The groups take turns every millisecond instead of spinning.
+    assert_eq!(budget.strategy(), Strategy::Multiplex(MIN_SLOT));
+    assert_eq!(done, Some(()));
+    assert_eq!(recorders[0].transitions(), [on(0), off(1), on(2)]);
+}
+
+#[test]
+fn port() {
+    static WRITES: Mutex<Vec<(u32, u32)>> = Mutex::new(Vec::new());
+    fn write(set: u32, clear: u32) {
+        WRITES.lock().unwrap().push((set, clear));
+    }
+
+    let _simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let masks = [
+        1 << 3,
+        1 << 4,
+        1 << 21,
+        1 << 20,
+        1 << 10,
+        1 << 7,
+        1 << 6,
+        1 << 5,
+    ];
+    let mut storeys = Storeys::new(switches).with_port(Port::low_active(masks, write));
+
+    storeys.set_frame(Frame::from_art("##......"));
+
+    // The LEDs are switched on by clearing their outputs.
+    let on = 1 << 3 | 1 << 4;
+    let off = 1 << 21 | 1 << 20 | 1 << 10 | 1 << 7 | 1 << 6 | 1 << 5;
+    assert_eq!(*WRITES.lock().unwrap(), [(off, on)]);
+    assert!(recorders[1].transitions()[0].is_on());
+    assert_eq!(storeys.frame(), Frame::from_art("##......"));
+}
+
+#[test]
+fn port_replaces_pin_writes() {
+    static WRITES: Mutex<Vec<(u32, u32)>> = Mutex::new(Vec::new());
+    static PIN_WRITES: AtomicUsize = AtomicUsize::new(0);
+    fn write(set: u32, clear: u32) {
+        WRITES.lock().unwrap().push((set, clear));
+    }
+
+    struct Pin;
+    impl ErrorType for Pin {
+        type Error = Infallible;
+    }
+    impl OutputPin for Pin {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            PIN_WRITES.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            PIN_WRITES.fetch_add(1, Ordering::Relaxed);
+            Ok(())
+        }
+    }
+
+    let leds = [(); 2].map(|()| LowActiveSwitch::new(Pin));
+    let port = Port::low_active([0b01, 0b10], write);
+    let mut storeys = Storeys::new(leds).with_port(port);
+    PIN_WRITES.store(0, Ordering::Relaxed);
+
+    storeys.set_frame(Frame::from_art("#."));
+    storeys.all_off();
+
+    // Only the port writes the outputs. The switches just follow along.
+    assert_eq!(PIN_WRITES.load(Ordering::Relaxed), 0);
+    assert_eq!(*WRITES.lock().unwrap(), [(0b10, 0b01), (0b11, 0)]);
+    assert_eq!(storeys.frame(), Frame::EMPTY);
+}
+
+#[test]
+fn commit_at() {
+    let simulation = Simulation::new();
+    let (switches, recorders) = mock::switches::<8>();
+    let mut storeys = Storeys::new(switches);
+
+    // Preparing the next frame does not show it.
+    storeys.set_frame(Frame::D1);
+    storeys.set_back_buffer(Frame::D2);
+    storeys.back_buffer_mut().set(2, true);
+    assert_eq!(storeys.frame(), Frame::D1);
+
+    let done = simulation.run_for(
+        storeys.commit_at(Instant::from_millis(100)),
+        Duration::from_secs(1),
+    );
+
+    assert_eq!(done, Some(()));
+    assert_eq!(storeys.frame(), Frame::from_art(".##....."));
+    assert_eq!(recorders[0].transitions(), [on(0), off(100)]);
+    assert_eq!(recorders[1].transitions(), [on(100)]);
+    assert_eq!(storeys.back_buffer(), Frame::from_art(".##....."));
+}
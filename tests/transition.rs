@@ -0,0 +1,84 @@
+//! Host tests previewing transitions between frames.
+//!
+//! See "Tests on the host" in the README for running them.
+//! Print the previews with `-- --nocapture`.
+
+use embassy_time::Duration;
+use hakkaa::easing::Easing;
+use hakkaa::frame::{Direction, Frame};
+use hakkaa::mock;
+use hakkaa::transition::Transition;
+
+const DURATION: Duration = Duration::from_millis(400);
+const STEP: Duration = Duration::from_millis(100);
+
+fn preview(transition: Transition, from: &str, to: &str) -> Vec<String> {
+    let from: Frame<4> = from.parse().unwrap();
+    let to: Frame<4> = to.parse().unwrap();
+    let lines = mock::preview(transition.with_step(STEP).animation(from, to));
+    for line in &lines {
+        println!("{line}");
+    }
+    lines.iter().map(|line| line[..4].to_string()).collect()
+}
+
+#[test]
+fn crossfade() {
+    assert_eq!(
+        preview(Transition::crossfade(DURATION), "##..", "#.#."),
+        ["#*-.", "#++.", "#-*.", "#.#."]
+    );
+}
+
+#[test]
+fn wipe() {
+    assert_eq!(
+        preview(Transition::wipe(DURATION, Direction::Up), "####", "...."),
+        [".###", "..##", "...#", "...."]
+    );
+    assert_eq!(
+        preview(Transition::wipe(DURATION, Direction::Down), "....", "####"),
+        ["...#", "..##", ".###", "####"]
+    );
+}
+
+#[test]
+fn wipe_with_easing() {
+    // Easing in takes a quarter of the distance in the first half of the time.
+    assert_eq!(
+        preview(
+            Transition::wipe(DURATION, Direction::Up).with_easing(Easing::EaseIn),
+            "....",
+            "####"
+        ),
+        ["-...", "#...", "##-.", "####"]
+    );
+}
+
+#[test]
+fn dissolve() {
+    let lines = preview(Transition::dissolve(DURATION), "....", "####");
+
+    // One more LED switches with each step. The order is random, but fixed by the seed.
+    let lit: Vec<u32> = lines
+        .iter()
+        .map(|line| line.parse::<Frame<4>>().unwrap().count_on())
+        .collect();
+    assert_eq!(lit, [1, 2, 3, 4]);
+    assert_eq!(
+        lines,
+        preview(Transition::dissolve(DURATION), "....", "####")
+    );
+}
+
+#[test]
+fn slide() {
+    assert_eq!(
+        preview(Transition::slide(DURATION, Direction::Up), "##..", "..#."),
+        [".##.", "#.##", ".#.#", "..#."]
+    );
+    assert_eq!(
+        preview(Transition::slide(DURATION, Direction::Down), "...#", "..#."),
+        ["..#.", ".#..", "#..#", "..#."]
+    );
+}